@@ -1,4 +1,7 @@
 extends Area2D
+class_name Damage
+
+signal hit();
 
 @export var max_health: int;
 var current_health: int;
@@ -11,6 +14,8 @@ func _ready() -> void:
 
 func on_area_entered(_area: Area2D) -> void:
 	self.current_health -= 1;
+	GlobalSolverInterface.report_damage_taken();
+	self.hit.emit();
 	$ProgressBar.value = self.current_health;
 	if self.current_health <= 0:
 		self.get_tree().reload_current_scene.call_deferred();
@@ -4,13 +4,20 @@ extends Node2D
 
 @export var active_timer: Timer;
 @export var cooldown_timer: Timer;
+@export var damage: Damage;
 
 @export var effect: PackedScene;
 
 signal active_end();
 
+# set when an attack gets through while parrying, a parry into empty air is no miss.
+var hit_while_active: bool = false;
+
 func _ready() -> void:
 	self.active_timer.timeout.connect(self.active_end.emit);
+	# the active timer only runs out if nothing was parried while it was active.
+	self.active_timer.timeout.connect(self.on_active_timeout);
+	self.damage.hit.connect(self.on_hit);
 
 func _physics_process(_delta: float) -> void:
 	for key in buttons.keys():
@@ -20,6 +27,7 @@ func _physics_process(_delta: float) -> void:
 func try_parry(key: StringName) -> void:
 	if !self.cooldown_timer.is_stopped():
 		return;
+	self.hit_while_active = false;
 	self.enable(self.buttons[key]);
 	self.cooldown_timer.start();
 	self.active_timer.start();
@@ -29,10 +37,19 @@ func on_area_entered(area: Area2D) -> void:
 		self.stop_active();
 		self.cooldown_timer.stop();
 		area.queue_free();
+		GlobalSolverInterface.report_parry_success();
 		var instance = self.effect.instantiate();
 		self.add_child(instance);
 		instance.global_position.x = area.global_position.x;
 
+func on_hit() -> void:
+	if !self.active_timer.is_stopped():
+		self.hit_while_active = true;
+
+func on_active_timeout() -> void:
+	if self.hit_while_active:
+		GlobalSolverInterface.report_parry_missed();
+
 func stop_active() -> void:
 	self.active_timer.stop();
 	self.active_end.emit();
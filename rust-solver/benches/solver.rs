@@ -0,0 +1,92 @@
+// run with `cargo bench --features bench`, add `parallel` to compare with the
+// tracks evaluated on the rayon pool.
+//
+// a frame at 60 Hz is 16.6 ms and the game needs most of it, so the solver is
+// held to these budgets per frame on a desktop cpu:
+// - `solve` of a fresh request with 300 tracks, what horde mode runs with: 1 ms
+// - `solve` of a fresh request with 1000 tracks: 4 ms
+// - `step` with 300 tracks, averaged over the frames between requests: 0.05 ms
+// `possible_future_commits` and `apply_commit_claims` are the bulk of `solve`
+// and are measured on their own to see which one a regression comes from.
+use attack_scheduler::bench_support::Encounter;
+use attack_scheduler::bench_support::EncounterShape;
+use criterion::BatchSize;
+use criterion::BenchmarkId;
+use criterion::Criterion;
+use criterion::criterion_group;
+use criterion::criterion_main;
+use std::hint::black_box;
+
+const TRACK_COUNTS: [usize; 4] = [10, 100, 300, 1000];
+
+fn shape(tracks: usize) -> EncounterShape {
+    EncounterShape {
+        tracks,
+        attacks_per_track: 4,
+        request_len: 8,
+    }
+}
+
+fn possible_future_commits(c: &mut Criterion) {
+    let mut group = c.benchmark_group("possible_future_commits");
+    for tracks in TRACK_COUNTS {
+        let encounter = Encounter::new(shape(tracks));
+        group.bench_with_input(BenchmarkId::new("tracks", tracks), &encounter, |b, e| {
+            b.iter(|| black_box(e.possible_future_commits()));
+        });
+    }
+    for attacks_per_track in [1, 4, 16] {
+        let encounter = Encounter::new(EncounterShape {
+            attacks_per_track,
+            ..shape(100)
+        });
+        group.bench_with_input(
+            BenchmarkId::new("attacks_per_track", attacks_per_track),
+            &encounter,
+            |b, e| b.iter(|| black_box(e.possible_future_commits())),
+        );
+    }
+    group.finish();
+}
+
+fn apply_commit_claims(c: &mut Criterion) {
+    let mut group = c.benchmark_group("apply_commit_claims");
+    for request_len in [1, 8, 32, 128] {
+        let encounter = Encounter::new(EncounterShape {
+            request_len,
+            ..shape(300)
+        });
+        group.bench_with_input(
+            BenchmarkId::new("request_len", request_len),
+            &encounter,
+            |b, e| b.iter(|| black_box(e.apply_commit_claims())),
+        );
+    }
+    group.finish();
+}
+
+fn solve(c: &mut Criterion) {
+    let mut group = c.benchmark_group("solve");
+    for tracks in TRACK_COUNTS {
+        group.bench_function(BenchmarkId::new("fresh_request", tracks), |b| {
+            b.iter_batched(
+                || Encounter::new(shape(tracks)),
+                |mut encounter| {
+                    encounter.solve();
+                    encounter
+                },
+                BatchSize::LargeInput,
+            );
+        });
+    }
+    for tracks in TRACK_COUNTS {
+        let mut encounter = Encounter::new(shape(tracks));
+        group.bench_function(BenchmarkId::new("step", tracks), |b| {
+            b.iter(|| encounter.step());
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, possible_future_commits, apply_commit_claims, solve);
+criterion_main!(benches);
@@ -1,10 +1,22 @@
+use self::zone_use::ZoneUse;
 use crate::enemy_track::complement_attack_request::ComplementAttackRequest;
+use crate::solver::snapshot::SnapshotReader;
+use crate::solver::snapshot::SnapshotState;
+use crate::solver::snapshot::SnapshotWriter;
 
-#[derive(Debug)]
+pub mod zone_use;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Attack {
     duration: u64,
     active: Vec<u64>,
     active_request_frames: Vec<u64>,
+    cost: u64,
+    tier: u8,
+    filler: bool,
+    // no two tracks take up the same zone at once, attacks without zones
+    // can share every zone.
+    zones: Vec<ZoneUse>,
 }
 
 impl Attack {
@@ -36,7 +48,53 @@ impl Attack {
             duration,
             active,
             active_request_frames: request_frames,
+            cost: 0,
+            tier: 0,
+            filler: false,
+            zones: vec![],
+        }
+    }
+    #[must_use]
+    pub fn with_cost(mut self, cost: u64) -> Self {
+        self.cost = cost;
+        self
+    }
+    #[must_use]
+    pub fn get_cost(&self) -> u64 {
+        self.cost
+    }
+    #[must_use]
+    pub fn with_tier(mut self, tier: u8) -> Self {
+        self.tier = tier;
+        self
+    }
+    #[must_use]
+    pub fn get_tier(&self) -> u8 {
+        self.tier
+    }
+    #[must_use]
+    pub fn with_filler(mut self, filler: bool) -> Self {
+        self.filler = filler;
+        self
+    }
+    // none if a zone is taken up past the end of the attack.
+    #[must_use]
+    pub fn with_zones(mut self, zones: Vec<ZoneUse>) -> Option<Self> {
+        if zones.iter().any(|zone| zone.get_until() > self.duration) {
+            return None;
         }
+        self.zones = zones;
+        Some(self)
+    }
+    #[must_use]
+    pub fn get_zones(&self) -> &[ZoneUse] {
+        &self.zones
+    }
+    // fillers are never used to answer or make requests, an attack that does
+    // neither is always a filler.
+    #[must_use]
+    pub fn is_filler(&self) -> bool {
+        self.filler || (self.active.is_empty() && self.active_request_frames.is_empty())
     }
     #[must_use]
     pub fn get_full_duration(&self) -> u64 {
@@ -65,6 +123,25 @@ impl Attack {
     }
 }
 
+impl SnapshotState for Attack {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.duration);
+        writer.write(&self.active);
+        writer.write(&self.active_request_frames);
+        writer.write(&self.cost);
+        writer.write(&self.tier);
+        writer.write(&self.filler);
+        writer.write(&self.zones);
+    }
+    fn read(reader: &mut SnapshotReader) -> Option<Self> {
+        Self::new(reader.read()?, reader.read()?, reader.read()?)?
+            .with_cost(reader.read()?)
+            .with_tier(reader.read()?)
+            .with_filler(reader.read()?)
+            .with_zones(reader.read()?)
+    }
+}
+
 #[cfg(test)]
 mod attack_tests {
     use super::*;
@@ -86,6 +163,17 @@ mod attack_tests {
         assert!(a.is_none());
     }
 
+    #[test]
+    fn filler_without_frames() {
+        assert!(Attack::new_expect(10, vec![], vec![]).is_filler());
+        assert!(
+            Attack::new_expect(10, vec![2], vec![])
+                .with_filler(true)
+                .is_filler()
+        );
+        assert!(!Attack::new_expect(10, vec![], vec![4]).is_filler());
+    }
+
     #[test]
     fn test_offsetting() {
         let a = Attack::new_expect(30, vec![8, 10, 24], vec![4]);
@@ -0,0 +1,45 @@
+use crate::solver::snapshot::SnapshotReader;
+use crate::solver::snapshot::SnapshotState;
+use crate::solver::snapshot::SnapshotWriter;
+
+// zones of the arena an attack takes up from `from` until `until`, in frames
+// from the start of the attack. zones are the bits of a mask, the same way
+// lanes are the bits of a collision layer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ZoneUse {
+    zones: u32,
+    from: u64,
+    until: u64,
+}
+
+impl ZoneUse {
+    // none if it takes up no zone or no frame.
+    #[must_use]
+    pub fn new(zones: u32, from: u64, until: u64) -> Option<Self> {
+        (zones != 0 && from < until).then_some(Self { zones, from, until })
+    }
+    #[must_use]
+    pub fn get_zones(&self) -> u32 {
+        self.zones
+    }
+    #[must_use]
+    pub fn get_until(&self) -> u64 {
+        self.until
+    }
+    // the frames it takes up for an attack started on `start`.
+    #[must_use]
+    pub fn frames_from(&self, start: u64) -> std::ops::Range<u64> {
+        start + self.from..start + self.until
+    }
+}
+
+impl SnapshotState for ZoneUse {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.zones);
+        writer.write(&self.from);
+        writer.write(&self.until);
+    }
+    fn read(reader: &mut SnapshotReader) -> Option<Self> {
+        Self::new(reader.read()?, reader.read()?, reader.read()?)
+    }
+}
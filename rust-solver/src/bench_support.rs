@@ -0,0 +1,162 @@
+use crate::attack::Attack;
+use crate::enemy_track::EnemyTrack;
+use crate::enemy_track::complement_attack_request::ComplementAttackRequest;
+use crate::enemy_track::complement_attack_request::request_index::RequestIndex;
+use crate::enemy_track::future_move_commit::FutureMoveCommit;
+use crate::solver::Solver;
+use crate::solver::seeded_random::SeededRandom;
+use std::num::NonZeroI64;
+
+// how big an encounter the benchmarks build.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncounterShape {
+    pub tracks: usize,
+    pub attacks_per_track: usize,
+    // request frames on the attack of the lead.
+    pub request_len: usize,
+}
+
+// a solver with one lead that has just started its attack and `tracks` other
+// tracks that can complement it. everything is built from the shape alone so
+// every run measures the same work.
+#[derive(Debug)]
+pub struct Encounter {
+    solver: Solver,
+    random: SeededRandom,
+    lead_id: NonZeroI64,
+    // the first option of every track against a fresh request of the lead.
+    first_options: Vec<(NonZeroI64, FutureMoveCommit)>,
+}
+
+impl Encounter {
+    /// # Panics
+    ///
+    /// Panics if there are more tracks than fit in an `i64`.
+    #[must_use]
+    pub fn new(shape: EncounterShape) -> Self {
+        let request_frames = (1..=shape.request_len as u64)
+            .map(|frame| frame * 12)
+            .collect::<Vec<_>>();
+        let lead_duration = request_frames.last().copied().unwrap_or(0) + 40;
+        let lead_attack =
+            Attack::new(lead_duration, vec![lead_duration - 2], request_frames).unwrap();
+        let mut lead = EnemyTrack::new(vec![lead_attack]);
+        lead.commit_by_index(0, 0, 0);
+
+        let mut solver = Solver::new();
+        let lead_id = NonZeroI64::MAX;
+        solver.add_track(lead_id, lead);
+        solver.change_lead(lead_id);
+        for count in 1..=shape.tracks {
+            let attacks = (0..shape.attacks_per_track)
+                .map(|index| follower_attack(count + index))
+                .collect();
+            let id = NonZeroI64::new(i64::try_from(count).unwrap()).unwrap();
+            solver.add_track(id, EnemyTrack::new(attacks));
+        }
+
+        let mut encounter = Self {
+            solver,
+            random: SeededRandom::new(shape.tracks as u64),
+            lead_id,
+            first_options: vec![],
+        };
+        let request = encounter.lead_request();
+        let open = RequestIndex::new(&request);
+        encounter.first_options = encounter
+            .solver
+            .track_ids()
+            .filter(|id| *id != lead_id)
+            .filter_map(|id| {
+                let track = encounter.solver.get_track(id);
+                let mut options = track.possible_future_commits(&request, &open, 0);
+                options.next().map(|commit| (id, commit))
+            })
+            .collect();
+        encounter
+    }
+    fn lead_request(&self) -> ComplementAttackRequest {
+        self.solver
+            .get_track(self.lead_id)
+            .last_queued_attack_as_request(self.lead_id)
+            .unwrap()
+    }
+    // every commit every track could make for the request of the lead,
+    // returns how many there are.
+    #[must_use]
+    pub fn possible_future_commits(&self) -> usize {
+        let request = self.lead_request();
+        let open = RequestIndex::new(&request);
+        let now = self.solver.time_now_frames();
+        self.solver
+            .track_ids()
+            .map(|id| {
+                self.solver
+                    .get_track(id)
+                    .possible_future_commits(&request, &open, now)
+                    .count()
+            })
+            .sum()
+    }
+    // claims the first option of every track on a fresh request of the lead,
+    // returns the frame its claim ends on.
+    #[must_use]
+    pub fn apply_commit_claims(&self) -> u64 {
+        let mut request = self.lead_request();
+        for (id, commit) in &self.first_options {
+            request.apply_commit_claim(*id, self.solver.get_track(*id), commit);
+        }
+        request.claim_end_time()
+    }
+    pub fn solve(&mut self) {
+        self.solver.solve(&mut self.random);
+    }
+    // one frame of the game, the lead starts its attack again whenever it can.
+    pub fn step(&mut self) {
+        let now = self.solver.time_now_frames();
+        self.solver.update_latest_nonpast();
+        let lead = self.solver.get_track_mut(self.lead_id);
+        if lead.is_actionable_now(now, now) {
+            lead.commit_by_index(0, now, now);
+        }
+        self.solver.solve(&mut self.random);
+        self.solver.tick();
+    }
+}
+
+// a spread of attacks with their active frames at different offsets, the
+// longer ones hit three times and every third one asks for a complement of its own.
+fn follower_attack(seed: usize) -> Attack {
+    let duration = 20 + (seed % 4) as u64 * 10;
+    let first = 4 + (seed % 7) as u64 * 2;
+    let active = if first + 24 < duration {
+        vec![first, first + 12, first + 24]
+    } else {
+        vec![first]
+    };
+    let request_frames = if seed.is_multiple_of(3) {
+        vec![duration - 1]
+    } else {
+        vec![]
+    };
+    Attack::new(duration, active, request_frames).unwrap()
+}
+
+#[cfg(test)]
+mod bench_support_tests {
+    use super::*;
+
+    #[test]
+    fn encounters_have_work() {
+        let mut encounter = Encounter::new(EncounterShape {
+            tracks: 20,
+            attacks_per_track: 4,
+            request_len: 8,
+        });
+        assert!(encounter.possible_future_commits() > 0);
+        assert!(encounter.apply_commit_claims() > 0);
+        for _ in 0..200 {
+            encounter.step();
+        }
+    }
+}
@@ -0,0 +1,30 @@
+use attack_scheduler::replay::replay;
+use std::env;
+use std::fs;
+use std::process::ExitCode;
+
+// replays a recording saved from `SolverInterface.stop_recording`:
+//      cargo run --bin replay -- path/to/recording
+fn main() -> ExitCode {
+    let Some(path) = env::args().nth(1) else {
+        eprintln!("usage: replay <recording>");
+        return ExitCode::FAILURE;
+    };
+    let bytes = match fs::read(&path) {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            eprintln!("could not read {path}: {error}");
+            return ExitCode::FAILURE;
+        }
+    };
+    match replay(&bytes) {
+        Ok(frames) => {
+            println!("replayed {frames} frames, schedule matches and is sound");
+            ExitCode::SUCCESS
+        }
+        Err(error) => {
+            eprintln!("{error}");
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -1,19 +1,63 @@
 use self::complement_attack_request::ComplementAttackRequest;
+use self::complement_attack_request::commit_key::CommitKey;
 use self::enemy_track_attack_wrapper::EnemyTrackAttack;
 use crate::attack::Attack;
+use crate::enemy_track::complement_attack_request::request_index::RequestIndex;
 use crate::enemy_track::complement_attack_request::request_offset::RequestOffset;
 use crate::enemy_track::future_move_commit::FutureMoveCommit;
+use crate::enemy_track::resource_pool::ResourcePool;
+use crate::enemy_track::track_phases::TrackPhases;
+use crate::solver::SolverRandomState;
+use crate::solver::snapshot::SnapshotReader;
+use crate::solver::snapshot::SnapshotState;
+use crate::solver::snapshot::SnapshotWriter;
+use std::num::NonZeroI64;
 use std::ops::RangeFrom;
+use std::sync::Arc;
 
 pub mod complement_attack_request;
 mod enemy_track_attack_wrapper;
 pub mod future_move_commit;
+pub mod resource_pool;
+pub mod track_phases;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct EnemyTrack {
-    attacks: Vec<EnemyTrackAttack>,
+    // shared between copies of the track, which only copy it once they change it.
+    attacks: Arc<Vec<EnemyTrackAttack>>,
     attacks_validitiy: Vec<bool>,
     future_stack: Vec<FutureMoveCommit>,
+    resource_pool: Option<ResourcePool>,
+    phases: Option<TrackPhases>,
+}
+
+// the part of a track that changes while the solver runs. attacks can be
+// pushed, replaced and removed while it runs, so they are part of it.
+pub struct EnemyTrackState {
+    attacks: Vec<Attack>,
+    attacks_validitiy: Vec<bool>,
+    future_stack: Vec<FutureMoveCommit>,
+    resource_pool: Option<ResourcePool>,
+    phases: Option<TrackPhases>,
+}
+
+impl SnapshotState for EnemyTrackState {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.attacks);
+        writer.write(&self.attacks_validitiy);
+        writer.write(&self.future_stack);
+        writer.write(&self.resource_pool);
+        writer.write(&self.phases);
+    }
+    fn read(reader: &mut SnapshotReader) -> Option<Self> {
+        Some(Self {
+            attacks: reader.read()?,
+            attacks_validitiy: reader.read()?,
+            future_stack: reader.read()?,
+            resource_pool: reader.read()?,
+            phases: reader.read()?,
+        })
+    }
 }
 
 impl EnemyTrack {
@@ -26,11 +70,91 @@ impl EnemyTrack {
             .collect();
         let attacks_validitiy = (0..attacks.len()).map(|_| true).collect();
         Self {
-            attacks,
+            attacks: Arc::new(attacks),
             attacks_validitiy,
             future_stack: vec![],
+            resource_pool: None,
+            phases: None,
         }
     }
+    pub fn set_resource_pool(&mut self, pool: Option<ResourcePool>) {
+        self.resource_pool = pool;
+    }
+    // the resource pool as it will be once every commit on the future stack
+    // has spent its cost.
+    fn projected_resource_pool(&self) -> Option<ResourcePool> {
+        self.resource_pool.map(|mut pool| {
+            for commit in &self.future_stack {
+                pool.spend_at(
+                    commit.get_start_frame(),
+                    self.get_attack(commit.get_index()).get_cost(),
+                );
+            }
+            pool
+        })
+    }
+    fn can_afford(pool: Option<&ResourcePool>, attack: &Attack, start_frame: u64) -> bool {
+        pool.is_none_or(|pool| pool.can_afford(start_frame, attack.get_cost()))
+    }
+    pub fn set_phases(&mut self, phases: Option<TrackPhases>) {
+        self.phases = phases;
+    }
+    #[must_use]
+    pub fn get_phases(&self) -> Option<&TrackPhases> {
+        self.phases.as_ref()
+    }
+    // returns false if the track has no such phase.
+    pub fn enter_phase(&mut self, phase: usize, now: u64) -> bool {
+        self.phases
+            .as_mut()
+            .is_some_and(|phases| phases.enter(phase, now))
+    }
+    // enters the phase a transition of the current phase points to, returns
+    // true if the phase changed.
+    pub fn update_phase(&mut self, now: u64) -> bool {
+        let Some(phases) = &mut self.phases else {
+            return false;
+        };
+        phases
+            .due_transition(now)
+            .is_some_and(|phase| phases.enter(phase, now))
+    }
+    // how likely an attack is to be picked, 1 for every attack on tracks without phases.
+    #[must_use]
+    pub fn attack_weight(&self, index: usize) -> u32 {
+        self.phases
+            .as_ref()
+            .map_or(1, |phases| phases.weight(index))
+    }
+    #[must_use]
+    pub fn is_lead_eligible(&self) -> bool {
+        self.phases
+            .as_ref()
+            .is_none_or(TrackPhases::is_lead_eligible)
+    }
+    // takes one of the options, picked by the weights of their attacks.
+    pub fn take_weighted(
+        &self,
+        options: &mut Vec<FutureMoveCommit>,
+        random: &mut impl SolverRandomState,
+    ) -> FutureMoveCommit {
+        let weight = |commit: &FutureMoveCommit| self.attack_weight(commit.get_index()) as usize;
+        let mut roll = random.next_in_range(options.iter().map(weight).sum());
+        let index = options
+            .iter()
+            .position(|commit| {
+                if roll < weight(commit) {
+                    return true;
+                }
+                roll -= weight(commit);
+                false
+            })
+            .unwrap_or(0);
+        options.swap_remove(index)
+    }
+    fn is_enabled(&self, index: usize) -> bool {
+        self.attacks_validitiy.get(index).is_some_and(|v| *v) && self.attack_weight(index) > 0
+    }
     pub fn set_validity(&mut self, index: usize, valid: bool) {
         self.attacks_validitiy[index] = valid;
     }
@@ -38,37 +162,115 @@ impl EnemyTrack {
         self.attacks_validitiy.fill(valid);
     }
     pub fn commit_valid(&self, commit: &FutureMoveCommit) -> bool {
-        self.attacks_validitiy
-            .get(commit.get_index())
-            .is_some_and(|v| *v)
+        self.is_enabled(commit.get_index())
     }
     fn valid_attacks(&self) -> impl Iterator<Item = &EnemyTrackAttack> {
         self.attacks
             .iter()
-            .zip(&self.attacks_validitiy)
-            .filter_map(|(attack, valid)| if *valid { Some(attack) } else { None })
+            .filter(|attack| self.is_enabled(attack.get_index()))
     }
-    fn possible_now_moves_iter(
-        &self,
-        request: &ComplementAttackRequest,
-        offset: &RequestOffset,
+    // the first active frame lands on `request_frame`, every later one before
+    // the claim end has to land on another open request frame.
+    fn possible_now_moves_iter<'a>(
+        &'a self,
+        open: &'a RequestIndex,
         request_frame: u64,
-        time_now: u64,
-    ) -> impl Iterator<Item = FutureMoveCommit> {
-        let first_actionable = self.first_actionable_frame(time_now);
-
+        first_actionable: u64,
+        pool: Option<ResourcePool>,
+    ) -> impl Iterator<Item = FutureMoveCommit> + 'a {
         self.valid_attacks()
+            .filter(|attack| !attack.get_attack().is_filler())
+            .filter(move |attack| open.covers(request_frame, attack.get_followup_offsets()))
             .filter_map(move |attack| attack.start_frame_and_index(request_frame, first_actionable))
+            .filter(move |(attack, start_frame)| {
+                Self::can_afford(pool.as_ref(), self.get_attack(*attack), *start_frame)
+            })
             .filter_map(move |(attack, start_frame)| {
                 FutureMoveCommit::try_create(attack, start_frame, first_actionable)
             })
-            .filter(|future_instance| {
-                future_instance.can_meet_request_followup(self, request, offset)
-            })
+    }
+    // the attack of a commit that has already started, it can not be changed
+    // or removed until that commit is over.
+    fn started_attack(&self, now: u64) -> Option<usize> {
+        self.future_stack
+            .first()
+            .filter(|commit| commit.get_start_frame() <= now)
+            .map(FutureMoveCommit::get_index)
+    }
+    // the new attack is valid, has a weight of 1 in every phase and gets the
+    // next index.
+    pub fn push_attack(&mut self, attack: Attack) -> usize {
+        let index = self.attacks.len();
+        Arc::make_mut(&mut self.attacks).push(EnemyTrackAttack::new(attack, index));
+        self.attacks_validitiy.push(true);
+        if let Some(phases) = &mut self.phases {
+            phases.push_attack(index);
+        }
+        index
+    }
+    // false if the index is out of range or the attack is in use by a started commit.
+    pub fn can_change_attack(&self, index: usize, now: u64) -> bool {
+        index < self.attacks.len() && self.started_attack(now) != Some(index)
+    }
+    // the first commit that uses the attack and everything planned after it,
+    // which is what changing the attack drops.
+    pub fn commits_from_use_of(&self, index: usize) -> impl Iterator<Item = &FutureMoveCommit> {
+        let position = self
+            .future_stack
+            .iter()
+            .position(|commit| commit.get_index() == index)
+            .unwrap_or(self.future_stack.len());
+        self.future_stack[position..].iter()
+    }
+    // commits that have not started yet and use the attack are dropped, along
+    // with everything planned after them. returns false if the attack can not
+    // be changed. the request frames they took stay taken, `Solver::replace_attack`
+    // gives them back.
+    pub fn replace_attack(&mut self, index: usize, attack: Attack, now: u64) -> bool {
+        if !self.can_change_attack(index, now) {
+            return false;
+        }
+        self.drop_commits_using(index);
+        Arc::make_mut(&mut self.attacks)[index] = EnemyTrackAttack::new(attack, index);
+        true
+    }
+    // attacks after the removed one move down by one index, the same way a
+    // `Vec` would, and commits are updated to match. commits are dropped the
+    // same way as in `replace_attack`.
+    pub fn remove_attack(&mut self, index: usize, now: u64) -> bool {
+        if !self.can_change_attack(index, now) {
+            return false;
+        }
+        self.drop_commits_using(index);
+        let attacks = Arc::make_mut(&mut self.attacks);
+        attacks.remove(index);
+        self.attacks_validitiy.remove(index);
+        if let Some(phases) = &mut self.phases {
+            phases.remove_attack(index);
+        }
+        for (position, attack) in attacks.iter_mut().enumerate().skip(index) {
+            attack.set_index(position);
+        }
+        for commit in &mut self.future_stack {
+            if commit.get_index() > index {
+                commit.set_index(commit.get_index() - 1);
+            }
+        }
+        true
+    }
+    fn drop_commits_using(&mut self, index: usize) {
+        let kept = self.future_stack.len() - self.commits_from_use_of(index).count();
+        self.future_stack.truncate(kept);
+    }
+    pub fn attacks(&self) -> impl ExactSizeIterator<Item = &Attack> {
+        self.attacks.iter().map(EnemyTrackAttack::get_attack)
     }
     pub fn get_attack(&self, index: usize) -> &Attack {
         self.attacks[index].get_attack()
     }
+    pub fn future_commits(&self) -> impl Iterator<Item = &FutureMoveCommit> {
+        self.future_stack.iter()
+    }
     pub fn latest_nonpast_commit(&self) -> Option<&FutureMoveCommit> {
         self.future_stack.first()
     }
@@ -80,49 +282,130 @@ impl EnemyTrack {
         } else {
             return;
         }
-        self.future_stack.remove(0);
+        let past = self.future_stack.remove(0);
+        if let Some(phases) = &mut self.phases {
+            phases.note_attack_performed();
+        }
+        let cost = self.get_attack(past.get_index()).get_cost();
+        if let Some(pool) = &mut self.resource_pool {
+            pool.spend_at(past.get_start_frame(), cost);
+        }
     }
-    #[must_use]
-    pub fn possible_now_commits(
+    // `open` has to be built from the current state of `request`.
+    pub fn possible_future_commits<'a>(
+        &'a self,
+        request: &'a ComplementAttackRequest,
+        open: &'a RequestIndex,
+        time_now: u64,
+    ) -> impl Iterator<Item = FutureMoveCommit> + 'a {
+        let first_actionable = self.first_actionable_frame(time_now);
+        let pool = self.projected_resource_pool();
+        std::iter::successors(Some(RequestOffset::new_default()), |offset| {
+            request.skip(RequestOffset::new(offset.get()))
+        })
+        .filter_map(|offset| request.first_req_frame(&offset))
+        .flat_map(move |request_frame| {
+            self.possible_now_moves_iter(open, request_frame, first_actionable, pool)
+        })
+    }
+    // commits for every attack that could be started as soon as this track is
+    // actionable, used to plan ahead for a lead track without any request.
+    pub fn possible_lead_commits(
         &self,
-        request: &ComplementAttackRequest,
-        offset: &RequestOffset,
         time_now: u64,
-    ) -> Vec<FutureMoveCommit> {
-        if let Some(request_frame) = request.first_req_frame(offset) {
-            self.possible_now_moves_iter(request, offset, request_frame, time_now)
-                .collect()
-        } else {
-            vec![]
-        }
+    ) -> impl Iterator<Item = FutureMoveCommit> + '_ {
+        let start_frame = self.first_actionable_frame(time_now);
+        let pool = self.projected_resource_pool();
+        self.valid_attacks()
+            .filter(|attack| !attack.get_attack().is_filler())
+            .filter(|attack| attack.get_attack().get_full_duration() > 0)
+            .filter(move |attack| Self::can_afford(pool.as_ref(), attack.get_attack(), start_frame))
+            .filter_map(move |attack| {
+                FutureMoveCommit::try_create(attack.get_index(), start_frame, start_frame)
+            })
     }
-    pub fn possible_future_commits(
+    // filler commits that can start now without running past `max_duration`
+    // frames, or into the next planned commit.
+    pub fn possible_filler_commits(
         &self,
-        request: &ComplementAttackRequest,
         time_now: u64,
-    ) -> Vec<FutureMoveCommit> {
-        let mut offset = RequestOffset::new_default();
-        let mut collection = self.possible_now_commits(request, &offset, time_now);
-        while let Some(tmp) = request.skip(offset) {
-            offset = tmp;
-            let mut skipped_add = self.possible_now_commits(request, &offset, time_now);
-            collection.append(&mut skipped_add);
-        }
-        collection
+        max_duration: u64,
+    ) -> impl Iterator<Item = FutureMoveCommit> + '_ {
+        // a commit that has started leaves no gap at all.
+        let gap_end = match self.future_stack.first() {
+            Some(next) if next.get_start_frame() <= time_now => 0,
+            Some(next) => next.get_start_frame(),
+            None => u64::MAX,
+        };
+        // a filler in front of planned commits must not take resources they need.
+        let affordable = move |attack: &Attack| {
+            attack.get_cost() == 0
+                || (self.future_stack.is_empty()
+                    && Self::can_afford(self.resource_pool.as_ref(), attack, time_now))
+        };
+        self.valid_attacks()
+            .filter(move |attack| {
+                let attack = attack.get_attack();
+                attack.is_filler()
+                    && attack.get_full_duration() > 0
+                    && attack.get_full_duration() <= max_duration
+                    && time_now.saturating_add(attack.get_full_duration()) <= gap_end
+                    && affordable(attack)
+            })
+            .filter_map(move |attack| {
+                FutureMoveCommit::try_create(attack.get_index(), time_now, time_now)
+            })
+    }
+    // puts a filler in front of any planned commits, should only be given
+    // commits from `possible_filler_commits`.
+    pub fn commit_filler(&mut self, commit: FutureMoveCommit) {
+        self.future_stack.insert(0, commit);
+    }
+    #[must_use]
+    pub fn last_commit_is_filler(&self) -> bool {
+        self.last_future_stack_item()
+            .is_some_and(|commit| self.get_attack(commit.get_index()).is_filler())
     }
     fn last_future_stack_item(&self) -> Option<&FutureMoveCommit> {
         self.future_stack.last()
     }
     pub fn get_commit_as_request(
         &self,
+        track_id: NonZeroI64,
         commit: &FutureMoveCommit,
     ) -> Option<ComplementAttackRequest> {
         self.get_attack(commit.get_index())
             .to_request(commit.get_start_frame())
+            .map(|request| request.with_source(CommitKey::new(track_id, commit.get_start_frame())))
     }
-    pub fn last_queued_attack_as_request(&self) -> Option<ComplementAttackRequest> {
+    // the same as `last_queued_attack_as_request`, but keeps the memory of
+    // `request`. returns false if there is no request to make.
+    pub fn last_queued_attack_into_request(
+        &self,
+        track_id: NonZeroI64,
+        request: &mut ComplementAttackRequest,
+    ) -> bool {
+        let Some(commit) = self.last_future_stack_item() else {
+            return false;
+        };
+        let attack = self.get_attack(commit.get_index());
+        let start_frame = commit.get_start_frame();
+        if !request.reset(
+            attack.active_request_frames(),
+            attack.get_full_duration(),
+            start_frame,
+        ) {
+            return false;
+        }
+        request.set_source(CommitKey::new(track_id, start_frame));
+        true
+    }
+    pub fn last_queued_attack_as_request(
+        &self,
+        track_id: NonZeroI64,
+    ) -> Option<ComplementAttackRequest> {
         self.last_future_stack_item()
-            .and_then(|commit| self.get_commit_as_request(commit))
+            .and_then(|commit| self.get_commit_as_request(track_id, commit))
     }
     #[must_use]
     pub fn first_actionable_frame(&self, time_now: u64) -> u64 {
@@ -132,15 +415,37 @@ impl EnemyTrack {
         }
     }
     //FIXME: in future, make sure commits are checked to be valid before allowing
-    pub fn commit(&mut self, request: &mut ComplementAttackRequest, commit: FutureMoveCommit) {
-        request.apply_commit_claim(self, &commit);
+    pub fn commit(
+        &mut self,
+        track_id: NonZeroI64,
+        request: &mut ComplementAttackRequest,
+        commit: FutureMoveCommit,
+    ) {
+        request.apply_commit_claim(track_id, self, &commit);
         self.future_stack.push(commit);
     }
+    // the commits that use disabled attacks and have not started yet.
+    pub fn invalid_commits(&self, now: u64) -> impl Iterator<Item = &FutureMoveCommit> {
+        self.future_stack
+            .iter()
+            .filter(move |commit| commit.get_start_frame() > now && !self.commit_valid(commit))
+    }
+    pub fn drop_commit_at(&mut self, start_frame: u64) {
+        self.future_stack
+            .retain(|commit| commit.get_start_frame() != start_frame);
+    }
     pub fn is_actionable_now(&self, start_time: u64, time_now: u64) -> bool {
         self.first_actionable_frame(time_now) <= start_time
     }
     pub fn commit_by_index(&mut self, attack_index: usize, start_time: u64, time_now: u64) -> bool {
-        if !self.attacks_validitiy.get(attack_index).is_some_and(|v| *v) {
+        if !self.is_enabled(attack_index) {
+            return false;
+        }
+        if !Self::can_afford(
+            self.projected_resource_pool().as_ref(),
+            self.get_attack(attack_index),
+            start_time,
+        ) {
             return false;
         }
         let maybe_commit = FutureMoveCommit::try_create(
@@ -155,31 +460,49 @@ impl EnemyTrack {
         }
         false
     }
-    // reset all non-current moves on the future stack.
-    // DOES NOT RESET ANYTHING THAT IS CURRENT EVEN IF THE ACTION IS NO LONGER
-    // VALID, AS THAT ACTION HAS ALREADY BEEN DONE AND CAN NOT BE BROKEN
-    // HALFWAY.
-    pub fn reset_non_current(&mut self, now: u64) {
-        let now_commit = if self.future_stack.is_empty() {
-            None
-        } else {
-            let n = self.future_stack.swap_remove(0);
-            if n.get_start_frame().le(&now) {
-                Some(n)
-            } else {
-                None
-            }
-        };
-        self.future_stack.clear();
-        if let Some(commit) = now_commit {
-            self.future_stack.push(commit);
+    // writes the same thing as an `EnemyTrackState` would, without copying.
+    pub fn write_state(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.attacks.len());
+        for attack in self.attacks() {
+            writer.write(attack);
+        }
+        writer.write(&self.attacks_validitiy);
+        writer.write(&self.future_stack);
+        writer.write(&self.resource_pool);
+        writer.write(&self.phases);
+    }
+    #[must_use]
+    pub fn can_restore_state(state: &EnemyTrackState) -> bool {
+        state.attacks_validitiy.len() == state.attacks.len()
+            && state
+                .future_stack
+                .iter()
+                .all(|commit| commit.get_index() < state.attacks.len())
+    }
+    // the attacks are only copied if they changed since the state was written.
+    pub fn restore_state(&mut self, state: EnemyTrackState) {
+        if !self.attacks().eq(&state.attacks) {
+            self.attacks = Arc::new(
+                state
+                    .attacks
+                    .into_iter()
+                    .zip(RangeFrom { start: 0 })
+                    .map(|(attack, index)| EnemyTrackAttack::new(attack, index))
+                    .collect(),
+            );
         }
+        self.attacks_validitiy = state.attacks_validitiy;
+        self.future_stack = state.future_stack;
+        self.resource_pool = state.resource_pool;
+        self.phases = state.phases;
     }
 }
 
 #[cfg(test)]
 mod enemy_track_tests {
     use super::*;
+    use crate::enemy_track::track_phases::Phase;
+    use crate::solver::seeded_random::SeededRandom;
 
     impl From<&Attack> for ComplementAttackRequest {
         fn from(value: &Attack) -> Self {
@@ -193,18 +516,57 @@ mod enemy_track_tests {
     }
 
     impl EnemyTrack {
+        #[must_use]
+        pub fn possible_now_commits(
+            &self,
+            request: &ComplementAttackRequest,
+            offset: &RequestOffset,
+            time_now: u64,
+        ) -> Vec<FutureMoveCommit> {
+            let open = RequestIndex::new(request);
+            let first_actionable = self.first_actionable_frame(time_now);
+            request
+                .first_req_frame(offset)
+                .map(|request_frame| {
+                    self.possible_now_moves_iter(
+                        &open,
+                        request_frame,
+                        first_actionable,
+                        self.projected_resource_pool(),
+                    )
+                    .collect()
+                })
+                .unwrap_or_default()
+        }
         #[must_use]
         pub fn possible_now_moves(
             &self,
             request: &ComplementAttackRequest,
             offset: &RequestOffset,
         ) -> Vec<&EnemyTrackAttack> {
-            if let Some(request_frame) = request.first_req_frame(offset) {
-                self.possible_now_moves_iter(request, offset, request_frame, 0)
-                    .map(|commit| &self.attacks[commit.get_index()])
-                    .collect()
+            self.possible_now_commits(request, offset, 0)
+                .iter()
+                .map(|commit| &self.attacks[commit.get_index()])
+                .collect()
+        }
+        // reset all non-current moves on the future stack.
+        // DOES NOT RESET ANYTHING THAT IS CURRENT EVEN IF THE ACTION IS NO LONGER
+        // VALID, AS THAT ACTION HAS ALREADY BEEN DONE AND CAN NOT BE BROKEN
+        // HALFWAY.
+        pub fn reset_non_current(&mut self, now: u64) {
+            let now_commit = if self.future_stack.is_empty() {
+                None
             } else {
-                vec![]
+                let n = self.future_stack.swap_remove(0);
+                if n.get_start_frame().le(&now) {
+                    Some(n)
+                } else {
+                    None
+                }
+            };
+            self.future_stack.clear();
+            if let Some(commit) = now_commit {
+                self.future_stack.push(commit);
             }
         }
     }
@@ -217,9 +579,13 @@ mod enemy_track_tests {
         expected_len: usize,
         expected_next_unclaimed: bool,
     ) -> Option<RequestOffset> {
-        let mut commits = track.possible_now_commits(&request, &offset, 0);
+        let mut commits = track.possible_now_commits(request, &offset, 0);
         assert_eq!(commits.len(), expected_len);
-        track.commit(request, commits.swap_remove(take_option_index));
+        track.commit(
+            NonZeroI64::MIN,
+            request,
+            commits.swap_remove(take_option_index),
+        );
         let tmp = request.next_unclaimed(offset);
         assert_eq!(tmp.is_some(), expected_next_unclaimed);
         tmp
@@ -338,7 +704,7 @@ mod enemy_track_tests {
         commit_and_assert(&mut mock_request, offset, &mut mock_track, 0, 1, false);
         mock_track.update_latest_nonpast(18);
         mock_track.reset_non_current(18);
-        assert!(mock_track.latest_nonpast_commit().is_some()); 
+        assert!(mock_track.latest_nonpast_commit().is_some());
         mock_track.update_latest_nonpast(21);
         mock_track.reset_non_current(21);
         assert!(mock_track.latest_nonpast_commit().is_none());
@@ -358,14 +724,161 @@ mod enemy_track_tests {
         offset = commit_and_assert(&mut mock_request, offset, &mut mock_track, 1, 2, true).unwrap();
         commit_and_assert(&mut mock_request, offset, &mut mock_track, 0, 1, false);
         mock_track.update_latest_nonpast(18);
-        assert!(mock_track.latest_nonpast_commit().is_some()); 
-        for i in 19..=28{
+        assert!(mock_track.latest_nonpast_commit().is_some());
+        for i in 19..=28 {
             mock_track.update_latest_nonpast(i);
         }
         mock_track.reset_non_current(28);
         assert!(mock_track.latest_nonpast_commit().is_some());
     }
-    
+
+    #[test]
+    fn lead_commits_start_when_actionable() {
+        let mut mock_track = EnemyTrack::new(vec![
+            Attack::new_expect(10, vec![5], vec![]),
+            Attack::new_expect(0, vec![], vec![]),
+            Attack::new_expect(20, vec![5], vec![15]),
+        ]);
+        assert!(mock_track.commit_by_index(0, 3, 0));
+        mock_track.set_validity(0, false);
+
+        let commits = mock_track.possible_lead_commits(0).collect::<Vec<_>>();
+        assert_eq!(commits.len(), 1);
+        assert_eq!(commits[0].get_index(), 2);
+        assert_eq!(commits[0].get_start_frame(), 13);
+    }
+
+    #[test]
+    fn fillers_fit_into_gaps() {
+        let mut mock_track = EnemyTrack::new(vec![
+            Attack::new_expect(10, vec![5], vec![]),
+            Attack::new_expect(4, vec![], vec![]),
+            Attack::new_expect(8, vec![2], vec![]).with_filler(true),
+        ]);
+        assert_eq!(mock_track.possible_filler_commits(0, 20).count(), 2);
+        assert_eq!(mock_track.possible_filler_commits(0, 5).count(), 1);
+
+        assert!(mock_track.commit_by_index(0, 6, 0));
+        let mut fillers = mock_track
+            .possible_filler_commits(0, 20)
+            .collect::<Vec<_>>();
+        assert_eq!(fillers.len(), 1);
+        assert_eq!(fillers[0].get_index(), 1);
+        mock_track.commit_filler(fillers.swap_remove(0));
+        assert!(!mock_track.last_commit_is_filler());
+        assert!(mock_track.possible_filler_commits(1, 20).next().is_none());
+
+        // fillers are never used to answer requests.
+        let mock_request: ComplementAttackRequest = Attack::new_expect(40, vec![], vec![22]).into();
+        assert_commits_length(&mock_request, &mock_track, 1);
+    }
+
+    #[test]
+    fn excludes_unaffordable_attacks() {
+        let mut mock_track = EnemyTrack::new(vec![
+            Attack::new_expect(10, vec![5], vec![]).with_cost(6),
+            Attack::new_expect(10, vec![5], vec![]).with_cost(3),
+        ]);
+        mock_track.set_resource_pool(Some(ResourcePool::new(8, 0)));
+        assert!(mock_track.commit_by_index(0, 0, 0));
+
+        let mock_request: ComplementAttackRequest = Attack::new_expect(40, vec![], vec![20]).into();
+        assert_commits_length(&mock_request, &mock_track, 0);
+
+        mock_track.set_resource_pool(Some(ResourcePool::new(8, 1)));
+        assert_commits_length(&mock_request, &mock_track, 2);
+    }
+
+    #[test]
+    fn commit_by_index_rejects_unaffordable() {
+        let mut mock_track =
+            EnemyTrack::new(vec![Attack::new_expect(10, vec![5], vec![]).with_cost(6)]);
+        mock_track.set_resource_pool(Some(ResourcePool::new(10, 0)));
+        assert!(mock_track.commit_by_index(0, 0, 0));
+        assert!(!mock_track.commit_by_index(0, 10, 0));
+        // the spent cost must stay spent once the commit is in the past.
+        mock_track.update_latest_nonpast(10);
+        assert!(mock_track.latest_nonpast_commit().is_none());
+        assert!(!mock_track.commit_by_index(0, 12, 10));
+    }
+
+    #[test]
+    fn remove_attack_reindexes_commits() {
+        let mut mock_track = EnemyTrack::new(vec![
+            Attack::new_expect(10, vec![5], vec![]),
+            Attack::new_expect(10, vec![5], vec![]),
+            Attack::new_expect(10, vec![5], vec![]),
+        ]);
+        mock_track.set_validity(2, false);
+        assert!(mock_track.commit_by_index(0, 0, 0));
+        assert!(mock_track.commit_by_index(1, 10, 0));
+        // attack 0 has started, attack 1 has not.
+        assert!(!mock_track.remove_attack(0, 5));
+        assert!(!mock_track.remove_attack(3, 5));
+        assert!(mock_track.remove_attack(1, 5));
+        assert_eq!(mock_track.attacks().len(), 2);
+        assert_eq!(mock_track.future_commits().count(), 1);
+        assert!(!mock_track.commit_valid(&FutureMoveCommit::try_create(1, 10, 0).unwrap()));
+
+        let mut mock_track = EnemyTrack::new(vec![
+            Attack::new_expect(10, vec![5], vec![]),
+            Attack::new_expect(10, vec![5], vec![]),
+        ]);
+        assert!(mock_track.commit_by_index(1, 0, 0));
+        assert!(mock_track.remove_attack(0, 5));
+        assert_eq!(mock_track.latest_nonpast_commit().unwrap().get_index(), 0);
+        assert_eq!(mock_track.attacks[0].get_index(), 0);
+    }
+
+    #[test]
+    fn replace_and_push_attack() {
+        let mut mock_track = EnemyTrack::new(vec![Attack::new_expect(10, vec![5], vec![])]);
+        assert_eq!(
+            mock_track.push_attack(Attack::new_expect(20, vec![5], vec![])),
+            1
+        );
+        assert!(mock_track.commit_by_index(1, 0, 0));
+        assert!(mock_track.commit_by_index(0, 20, 0));
+        assert!(!mock_track.replace_attack(1, Attack::new_expect(30, vec![5], vec![]), 5));
+
+        // the planned commit is dropped with the attack, the started one stays.
+        assert!(mock_track.replace_attack(0, Attack::new_expect(30, vec![5], vec![]), 5));
+        assert_eq!(mock_track.future_commits().count(), 1);
+        assert_eq!(mock_track.latest_nonpast_commit().unwrap().get_index(), 1);
+        assert_eq!(mock_track.get_attack(0).get_full_duration(), 30);
+    }
+
+    #[test]
+    fn phases_disable_and_weight_attacks() {
+        let mut mock_track = EnemyTrack::new(vec![
+            Attack::new_expect(10, vec![5], vec![]),
+            Attack::new_expect(10, vec![5], vec![]),
+            Attack::new_expect(10, vec![5], vec![]),
+        ]);
+        mock_track.set_phases(TrackPhases::new(vec![Phase::new(vec![0, 1, 3], false)]));
+        assert!(!mock_track.is_lead_eligible());
+        assert!(!mock_track.commit_by_index(0, 0, 0));
+
+        let mut random = SeededRandom::new(3);
+        let mut picked = [0; 3];
+        for _ in 0..400 {
+            let mut options = (0..3)
+                .filter_map(|index| FutureMoveCommit::try_create(index, 0, 0))
+                .filter(|commit| mock_track.commit_valid(commit))
+                .collect::<Vec<_>>();
+            picked[mock_track
+                .take_weighted(&mut options, &mut random)
+                .get_index()] += 1;
+        }
+        assert_eq!(picked[0], 0);
+        assert!(picked[2] > picked[1] * 2);
+
+        // a move the track gains later can be picked right away.
+        let index = mock_track.push_attack(Attack::new_expect(10, vec![5], vec![]));
+        assert_eq!(mock_track.attack_weight(index), 1);
+        assert!(mock_track.commit_by_index(index, 0, 0));
+    }
+
     #[test]
     fn can_match_all_futures() {
         let mock_track = EnemyTrack::new(vec![
@@ -375,9 +888,12 @@ mod enemy_track_tests {
         ]);
 
         let mock_lead_track = Attack::new_expect(25, vec![], vec![10, 18]);
-        let mut mock_request: ComplementAttackRequest = mock_lead_track.into();
+        let mock_request: ComplementAttackRequest = mock_lead_track.into();
 
-        let can_meet = mock_track.possible_future_commits(&mut mock_request, 0);
+        let open = RequestIndex::new(&mock_request);
+        let can_meet = mock_track
+            .possible_future_commits(&mock_request, &open, 0)
+            .collect::<Vec<_>>();
         dbg!(&can_meet);
         assert_eq!(can_meet.len(), 3);
     }
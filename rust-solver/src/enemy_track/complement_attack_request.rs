@@ -1,27 +1,129 @@
+use self::commit_key::CommitKey;
+use self::request_frame::RequestFrame;
 use self::request_offset::RequestOffset;
 use crate::enemy_track::EnemyTrack;
 use crate::enemy_track::future_move_commit::FutureMoveCommit;
+use crate::solver::snapshot::SnapshotReader;
+use crate::solver::snapshot::SnapshotState;
+use crate::solver::snapshot::SnapshotWriter;
+use std::num::NonZeroI64;
 
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod claim_invariants;
+pub mod commit_key;
+pub mod request_frame;
+pub mod request_index;
 pub mod request_offset;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct ComplementAttackRequest {
+    // sorted, and every frame is in it once.
     request_frames: Vec<u64>,
     taken_requests: Vec<bool>,
+    // the commit that took each request frame, none if it is free or was declined.
+    claimants: Vec<Option<CommitKey>>,
+    // the commit each request frame came from, none for frames of the source.
+    origins: Vec<Option<CommitKey>>,
     claim_end_time: u64,
+    // claim end of the source, and of every commit applied since.
+    source_claim_end: u64,
+    claim_ends: Vec<(CommitKey, u64)>,
+    source: Option<CommitKey>,
+    complementers: Vec<NonZeroI64>,
+    // released request frames that stay declined until the frame paired with
+    // them, so what took them is not replaced right away.
+    held: Vec<(u64, u64)>,
 }
 
 impl ComplementAttackRequest {
     pub fn new(vec: &[u64], request_source_claim_end: u64, start_frame: u64) -> Option<Self> {
         if vec.is_empty() {
-            None
-        } else {
-            Some(Self {
-                taken_requests: vec.iter().map(|_| false).collect(),
-                request_frames: vec.iter().map(|x| x + start_frame).collect(),
-                claim_end_time: request_source_claim_end + start_frame,
-            })
+            return None;
+        }
+        let mut request = Self {
+            request_frames: Vec::with_capacity(vec.len()),
+            taken_requests: Vec::with_capacity(vec.len()),
+            claimants: Vec::with_capacity(vec.len()),
+            origins: Vec::with_capacity(vec.len()),
+            claim_end_time: request_source_claim_end + start_frame,
+            source_claim_end: request_source_claim_end + start_frame,
+            claim_ends: vec![],
+            source: None,
+            complementers: vec![],
+            held: vec![],
+        };
+        for frame in vec {
+            request.insert_frame(frame + start_frame, None);
+        }
+        Some(request)
+    }
+    // the same as `new`, but keeps the memory of the old request. returns
+    // false and leaves the request as it was if there are no request frames.
+    pub fn reset(&mut self, vec: &[u64], request_source_claim_end: u64, start_frame: u64) -> bool {
+        if vec.is_empty() {
+            return false;
+        }
+        self.request_frames.clear();
+        self.taken_requests.clear();
+        self.claimants.clear();
+        self.origins.clear();
+        for frame in vec {
+            self.insert_frame(frame + start_frame, None);
         }
+        self.claim_end_time = request_source_claim_end + start_frame;
+        self.source_claim_end = request_source_claim_end + start_frame;
+        self.claim_ends.clear();
+        self.source = None;
+        self.complementers.clear();
+        self.held.clear();
+        true
+    }
+    // adds a frame `origin` asks for where it belongs in time, so the frames
+    // stay sorted no matter who asks in what order. a frame that is asked for
+    // already is kept once, with the origin that asked first.
+    fn insert_frame(&mut self, frame: u64, origin: Option<CommitKey>) {
+        let index = self.request_frames.partition_point(|other| *other < frame);
+        if self.request_frames.get(index) == Some(&frame) {
+            return;
+        }
+        self.request_frames.insert(index, frame);
+        self.taken_requests.insert(index, false);
+        self.claimants.insert(index, None);
+        self.origins.insert(index, origin);
+    }
+    #[must_use]
+    pub fn with_source(mut self, source: CommitKey) -> Self {
+        self.source = Some(source);
+        self
+    }
+    pub fn set_source(&mut self, source: CommitKey) {
+        self.source = Some(source);
+    }
+    #[must_use]
+    pub fn get_source(&self) -> Option<CommitKey> {
+        self.source
+    }
+    // every request frame in order, with where it came from and what took it.
+    pub fn frames(&self) -> impl Iterator<Item = RequestFrame> + '_ {
+        (0..self.request_frames.len()).map(|index| {
+            RequestFrame::new(
+                self.request_frames[index],
+                self.origins[index].or(self.source),
+                self.claimants[index],
+                self.taken_requests[index],
+            )
+        })
+    }
+    // the commit that asked for the request frame, none if there is no such frame.
+    #[must_use]
+    pub fn requester_of(&self, frame: u64) -> Option<CommitKey> {
+        let index = self.request_frames.binary_search(&frame).ok()?;
+        self.origins[index].or(self.source)
+    }
+    // every commit that was applied to the request and has not ended yet, in
+    // the order they were applied.
+    pub fn claim_holders(&self) -> impl Iterator<Item = CommitKey> + '_ {
+        self.claim_ends.iter().map(|(key, _)| *key)
     }
     pub(crate) fn first_req_frame(&self, request_state: &RequestOffset) -> Option<u64> {
         if self.taken_requests[request_state.get()] {
@@ -29,17 +131,6 @@ impl ComplementAttackRequest {
         }
         self.request_frames.get(request_state.get()).copied()
     }
-    pub(super) fn iter_skip_start(
-        &self,
-        request_state: &RequestOffset,
-    ) -> impl Iterator<Item = u64> {
-        self.request_frames
-            .iter()
-            .zip(&self.taken_requests)
-            .skip(request_state.get() + 1)
-            .filter_map(|(req, taken)| if *taken { None } else { Some(req) })
-            .copied()
-    }
     pub(crate) fn claim_end_time(&self) -> u64 {
         self.claim_end_time
     }
@@ -58,19 +149,139 @@ impl ComplementAttackRequest {
         }
         None
     }
-    pub fn apply_commit_claim(&mut self, track: &EnemyTrack, commit: &FutureMoveCommit) {
+    // marks the request frame as taken without anything claiming it.
+    pub fn decline(&mut self, request_state: &RequestOffset) {
+        if let Some(taken) = self.taken_requests.get_mut(request_state.get()) {
+            *taken = true;
+        }
+    }
+    pub fn note_complementer(&mut self, track_id: NonZeroI64) {
+        if !self.is_complementer(track_id) {
+            self.complementers.push(track_id);
+        }
+    }
+    #[must_use]
+    pub fn is_complementer(&self, track_id: NonZeroI64) -> bool {
+        self.complementers.contains(&track_id)
+    }
+    #[must_use]
+    pub fn complementer_count(&self) -> usize {
+        self.complementers.len()
+    }
+    // drops every request frame before `frame`, as nothing can start early
+    // enough to take them anymore, and the claims of commits that have ended.
+    // a request that is extended by planning ahead would grow forever otherwise.
+    pub fn forget_before(&mut self, frame: u64) {
+        let mut kept = 0;
+        for index in 0..self.request_frames.len() {
+            if self.request_frames[index] < frame {
+                continue;
+            }
+            self.request_frames.swap(kept, index);
+            self.taken_requests.swap(kept, index);
+            self.claimants.swap(kept, index);
+            self.origins.swap(kept, index);
+            kept += 1;
+        }
+        self.request_frames.truncate(kept);
+        self.taken_requests.truncate(kept);
+        self.claimants.truncate(kept);
+        self.origins.truncate(kept);
+        self.claim_ends.retain(|(_, end)| *end > frame);
+        self.held.retain(|(held, _)| *held >= frame);
+    }
+    // opens the held request frames again whose grace is over on `now`.
+    pub fn reopen_held(&mut self, now: u64) {
+        let (request_frames, taken_requests) = (&self.request_frames, &mut self.taken_requests);
+        self.held.retain(|(frame, reopen_from)| {
+            if *reopen_from > now {
+                return true;
+            }
+            if let Ok(index) = request_frames.binary_search(frame) {
+                taken_requests[index] = false;
+            }
+            false
+        });
+    }
+    // frames that are already taken keep their claimant.
+    fn claim(&mut self, index: usize, claimant: CommitKey) {
+        if !self.taken_requests[index] {
+            self.taken_requests[index] = true;
+            self.claimants[index] = Some(claimant);
+        }
+    }
+    // frees every request frame the commit took and removes the ones it added,
+    // returns the other commits that took the removed frames, as they have
+    // nothing left to complement. the freed frames are held until
+    // `reopen_from`, and only taken again after `reopen_held` opens them.
+    // releasing the source removes the frames without an origin, and the
+    // request is left without a source.
+    pub fn release(&mut self, key: CommitKey, reopen_from: u64) -> Vec<CommitKey> {
+        let mut orphaned = vec![];
+        let mut index = 0;
+        while index < self.request_frames.len() {
+            if self.origins[index].or(self.source) == Some(key) {
+                if let Some(claimant) = self.claimants[index]
+                    && claimant != key
+                    && !orphaned.contains(&claimant)
+                {
+                    orphaned.push(claimant);
+                }
+                let frame = self.request_frames.remove(index);
+                self.held.retain(|(held, _)| *held != frame);
+                self.taken_requests.remove(index);
+                self.claimants.remove(index);
+                self.origins.remove(index);
+                continue;
+            }
+            if self.claimants[index] == Some(key) {
+                self.claimants[index] = None;
+                self.held.push((self.request_frames[index], reopen_from));
+            }
+            index += 1;
+        }
+        if self.source == Some(key) {
+            self.source = None;
+            self.source_claim_end = 0;
+        }
+        self.claim_ends.retain(|(claimant, _)| *claimant != key);
+        self.claim_end_time = self
+            .claim_ends
+            .iter()
+            .map(|(_, end)| *end)
+            .fold(self.source_claim_end, u64::max);
+        let claim_ends = &self.claim_ends;
+        self.complementers.retain(|track_id| {
+            claim_ends
+                .iter()
+                .any(|(claimant, _)| claimant.get_track_id() == *track_id)
+        });
+        orphaned
+    }
+    pub fn apply_commit_claim(
+        &mut self,
+        track_id: NonZeroI64,
+        track: &EnemyTrack,
+        commit: &FutureMoveCommit,
+    ) {
+        let key = CommitKey::new(track_id, commit.get_start_frame());
         let mut index = 0;
         for active in commit.get_active_frames(track) {
             if active >= self.claim_end_time() {
                 break;
             }
-            while index < self.request_frames.len() {
-                if self.request_frames[index] == active {
-                    self.taken_requests[index] = true;
-                    break;
-                }
+            // stops at the first later frame, an active frame that misses
+            // must not use up the frames of the ones after it.
+            while self
+                .request_frames
+                .get(index)
+                .is_some_and(|frame| *frame < active)
+            {
                 index += 1;
             }
+            if self.request_frames.get(index) == Some(&active) {
+                self.claim(index, key);
+            }
         }
         let mut index = 0;
         // skip all indices that come before the current attack's claimed duration.
@@ -85,8 +296,7 @@ impl ComplementAttackRequest {
         let commit_end_frame = commit.get_end_frame(track);
         for other_request_frame in commit.get_request_frames(track) {
             if other_request_frame >= self.claim_end_time() {
-                self.request_frames.push(other_request_frame);
-                self.taken_requests.push(false);
+                self.insert_frame(other_request_frame, Some(key));
                 exceeded = true;
                 continue;
             }
@@ -98,7 +308,7 @@ impl ComplementAttackRequest {
                 if self.request_frames[index] == other_request_frame {
                     break;
                 }
-                self.taken_requests[index] = true;
+                self.claim(index, key);
                 index += 1;
             }
         }
@@ -110,78 +320,105 @@ impl ComplementAttackRequest {
                 if self.request_frames[index] >= commit_end_frame {
                     break;
                 }
-                self.taken_requests[index] = true;
+                self.claim(index, key);
                 index += 1;
             }
         }
+        self.claim_ends.push((key, commit_end_frame));
         self.claim_end_time = u64::max(commit_end_frame, self.claim_end_time);
     }
 }
 
+impl SnapshotState for ComplementAttackRequest {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.request_frames);
+        writer.write(&self.taken_requests);
+        writer.write(&self.claimants);
+        writer.write(&self.origins);
+        writer.write(&self.claim_end_time);
+        writer.write(&self.source_claim_end);
+        writer.write(&self.claim_ends);
+        writer.write(&self.source);
+        writer.write(&self.complementers);
+        writer.write(&self.held);
+    }
+    fn read(reader: &mut SnapshotReader) -> Option<Self> {
+        let request = Self {
+            request_frames: reader.read()?,
+            taken_requests: reader.read()?,
+            claimants: reader.read()?,
+            origins: reader.read()?,
+            claim_end_time: reader.read()?,
+            source_claim_end: reader.read()?,
+            claim_ends: reader.read()?,
+            source: reader.read()?,
+            complementers: reader.read()?,
+            held: reader.read()?,
+        };
+        let len = request.request_frames.len();
+        (request.taken_requests.len() == len
+            && request.claimants.len() == len
+            && request.origins.len() == len
+            && request.request_frames.is_sorted_by(|a, b| a < b))
+        .then_some(request)
+    }
+}
+
 #[cfg(test)]
 mod complement_attack_request_tests {
     use super::*;
+    use crate::attack::Attack;
+    use crate::enemy_track::complement_attack_request::request_index::RequestIndex;
+
+    fn open_frames(req: &ComplementAttackRequest) -> Vec<u64> {
+        let index = RequestIndex::new(req);
+        (0..req.claim_end_time())
+            .filter(|frame| index.is_open(*frame))
+            .collect()
+    }
 
     #[test]
     fn test_filter() {
-        let mut req = ComplementAttackRequest::new(&vec![20, 32, 40], 100, 0).unwrap();
+        let mut req = ComplementAttackRequest::new(&[20, 32, 40], 100, 0).unwrap();
         req.taken_requests[2] = true;
 
-        assert_eq!(
-            req.iter_skip_start(&RequestOffset::new_default())
-                .collect::<Vec<u64>>(),
-            vec![32]
-        );
+        assert_eq!(open_frames(&req), vec![20, 32]);
     }
 
     #[test]
     fn test_filter_first() {
-        let offset = RequestOffset::new_default();
-        let mut req = ComplementAttackRequest::new(&vec![20, 32, 40, 90], 100, 0).unwrap();
+        let mut req = ComplementAttackRequest::new(&[20, 32, 40, 90], 100, 0).unwrap();
         req.taken_requests[2] = true;
         req.taken_requests[0] = true;
 
-        assert_eq!(
-            req.iter_skip_start(&offset).collect::<Vec<u64>>(),
-            vec![32, 90]
-        );
+        assert_eq!(open_frames(&req), vec![32, 90]);
 
         req.taken_requests[2] = false;
 
-        assert_eq!(
-            req.iter_skip_start(&offset).collect::<Vec<u64>>(),
-            vec![32, 40, 90]
-        );
+        assert_eq!(open_frames(&req), vec![32, 40, 90]);
     }
 
     #[test]
     fn test_skip() {
         let mut offset = RequestOffset::new_default();
-        let mut req = ComplementAttackRequest::new(&vec![20, 32, 40, 90], 100, 0).unwrap();
+        let mut req = ComplementAttackRequest::new(&[20, 32, 40, 90], 100, 0).unwrap();
         req.taken_requests[2] = true;
         req.taken_requests[0] = true;
 
         offset = req.skip(offset).unwrap();
-        assert_eq!(req.iter_skip_start(&offset).collect::<Vec<u64>>(), vec![90]);
-
-        req.taken_requests[2] = false;
-
-        assert_eq!(
-            req.iter_skip_start(&offset).collect::<Vec<u64>>(),
-            vec![40, 90]
-        );
+        assert_eq!(req.first_req_frame(&offset), Some(32));
+        offset = req.skip(offset).unwrap();
+        assert_eq!(req.first_req_frame(&offset), Some(90));
+        assert!(req.skip(offset).is_none());
     }
 
     #[test]
     fn test_offset() {
         let mut offset = RequestOffset::new_default();
-        let mut req = ComplementAttackRequest::new(&vec![20, 32, 40, 90], 100, 20).unwrap();
+        let mut req = ComplementAttackRequest::new(&[20, 32, 40, 90], 100, 20).unwrap();
         req.taken_requests[2] = true;
 
-        assert_eq!(
-            req.iter_skip_start(&offset).collect::<Vec<u64>>(),
-            vec![52, 110]
-        );
+        assert_eq!(open_frames(&req), vec![40, 52, 110]);
 
         assert_eq!(req.first_req_frame(&offset), Some(40));
 
@@ -189,4 +426,134 @@ mod complement_attack_request_tests {
 
         assert_eq!(req.first_req_frame(&offset), Some(52));
     }
+
+    #[test]
+    fn forgets_past_frames() {
+        let key = CommitKey::new(NonZeroI64::new(1).unwrap(), 0);
+        let mut req = ComplementAttackRequest::new(&[20, 32, 40, 90], 100, 0).unwrap();
+        req.claim(1, key);
+        req.claim_ends.push((key, 35));
+        req.forget_before(35);
+        assert_eq!(req.request_frames, [40, 90]);
+        assert_eq!(req.taken_requests, [false, false]);
+        assert_eq!(req.claimants, [None, None]);
+        assert!(req.claim_ends.is_empty());
+        assert_eq!(req.claim_end_time(), 100);
+    }
+
+    #[test]
+    fn missed_active_frame_keeps_later_claims() {
+        let mut req = ComplementAttackRequest::new(&[0, 61], 62, 0).unwrap();
+        let id = NonZeroI64::new(1).unwrap();
+        let mut track = EnemyTrack::new(vec![Attack::new_expect(37, vec![0, 10], vec![])]);
+        track.commit(
+            id,
+            &mut req,
+            FutureMoveCommit::try_create(0, 51, 0).unwrap(),
+        );
+        assert_eq!(req.taken_requests, [false, true]);
+        assert_eq!(req.claimants[1], Some(CommitKey::new(id, 51)));
+    }
+
+    #[test]
+    fn merges_repeated_frames() {
+        let req = ComplementAttackRequest::new(&[32, 20, 20], 100, 5).unwrap();
+        assert_eq!(req.request_frames, [25, 37]);
+        assert_eq!(req.taken_requests.len(), 2);
+    }
+
+    #[test]
+    fn followers_extend_in_time_order() {
+        let mut req = ComplementAttackRequest::new(&[10], 20, 0).unwrap();
+        let (a, b) = (NonZeroI64::new(1).unwrap(), NonZeroI64::new(2).unwrap());
+        let mut track_a = EnemyTrack::new(vec![Attack::new_expect(30, vec![0], vec![15, 15, 25])]);
+        let mut track_b = EnemyTrack::new(vec![Attack::new_expect(30, vec![0], vec![20])]);
+        track_a.commit(a, &mut req, FutureMoveCommit::try_create(0, 10, 0).unwrap());
+        track_b.commit(b, &mut req, FutureMoveCommit::try_create(0, 25, 0).unwrap());
+
+        assert_eq!(req.request_frames, [10, 25, 35, 45]);
+        assert_eq!(req.taken_requests, [true, true, false, false]);
+        assert_eq!(
+            req.origins,
+            [
+                None,
+                Some(CommitKey::new(a, 10)),
+                Some(CommitKey::new(a, 10)),
+                Some(CommitKey::new(b, 25)),
+            ]
+        );
+        assert_eq!(req.claim_end_time(), 55);
+
+        // a request may ask for frames past its own claim end, the frames both
+        // followers add then land before the last one already in it.
+        let mut req = ComplementAttackRequest::new(&[10, 100], 20, 0).unwrap();
+        track_a.commit(a, &mut req, FutureMoveCommit::try_create(0, 10, 0).unwrap());
+        track_b.commit(b, &mut req, FutureMoveCommit::try_create(0, 45, 0).unwrap());
+        assert_eq!(req.request_frames, [10, 25, 35, 65, 100]);
+        assert_eq!(
+            req.origins,
+            [
+                None,
+                Some(CommitKey::new(a, 10)),
+                Some(CommitKey::new(a, 10)),
+                Some(CommitKey::new(b, 45)),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn release_reopens_and_orphans() {
+        let lead_key = CommitKey::new(NonZeroI64::new(1).unwrap(), 0);
+        let mut req = ComplementAttackRequest::new(&[20, 40], 50, 0)
+            .unwrap()
+            .with_source(lead_key);
+        let first_id = NonZeroI64::new(2).unwrap();
+        let mut first = EnemyTrack::new(vec![Attack::new_expect(40, vec![5], vec![38])]);
+        let second_id = NonZeroI64::new(3).unwrap();
+        let mut second = EnemyTrack::new(vec![Attack::new_expect(10, vec![5], vec![])]);
+
+        first.commit(
+            first_id,
+            &mut req,
+            FutureMoveCommit::try_create(0, 15, 0).unwrap(),
+        );
+        second.commit(
+            second_id,
+            &mut req,
+            FutureMoveCommit::try_create(0, 48, 0).unwrap(),
+        );
+        assert_eq!(req.request_frames, [20, 40, 53]);
+        assert_eq!(req.taken_requests, [true, false, true]);
+        assert_eq!(req.claim_end_time(), 58);
+
+        let orphaned = req.release(CommitKey::new(first_id, 15), 0);
+        assert_eq!(orphaned, [CommitKey::new(second_id, 48)]);
+        assert_eq!(req.request_frames, [20, 40]);
+        assert_eq!(req.taken_requests, [true, false]);
+        req.reopen_held(0);
+        assert_eq!(req.taken_requests, [false, false]);
+        assert_eq!(req.claim_end_time(), 58);
+        req.release(CommitKey::new(second_id, 48), 0);
+        assert_eq!(req.claim_end_time(), 50);
+        assert_eq!(req.get_source(), Some(lead_key));
+
+        // frames freed within the grace period are declined until it ends.
+        second.commit(
+            second_id,
+            &mut req,
+            FutureMoveCommit::try_create(0, 65, 0).unwrap(),
+        );
+        second.commit(
+            second_id,
+            &mut req,
+            FutureMoveCommit::try_create(0, 15, 0).unwrap(),
+        );
+        req.release(CommitKey::new(second_id, 15), 30);
+        req.reopen_held(29);
+        assert_eq!(req.taken_requests, [true, false]);
+        assert_eq!(req.claimants, [None, None]);
+        req.reopen_held(30);
+        assert_eq!(req.taken_requests, [false, false]);
+    }
 }
@@ -0,0 +1,239 @@
+use super::CommitKey;
+use super::ComplementAttackRequest;
+use crate::attack::Attack;
+use crate::enemy_track::EnemyTrack;
+use crate::enemy_track::future_move_commit::FutureMoveCommit;
+use std::num::NonZeroI64;
+
+// something done to a request, in the order the solver could do it.
+#[derive(Debug, Clone)]
+pub enum ClaimOp {
+    // a commit of the attack on a track of its own.
+    Commit { attack: Attack, start: u64 },
+    // releases the commit made by the `Commit` at this position, if there is one.
+    Release { commit: usize, reopen_from: u64 },
+    ReleaseSource { reopen_from: u64 },
+    ForgetBefore(u64),
+    ReopenHeld(u64),
+}
+
+// a request for the attack of the source started on frame 0, and what is done to it.
+#[derive(Debug, Clone)]
+pub struct ClaimCase {
+    pub source: Attack,
+    pub ops: Vec<ClaimOp>,
+}
+
+/// Runs the case and checks the request after every op.
+///
+/// # Panics
+///
+/// Panics on the first op that leaves the request broken.
+pub fn check_claims(case: &ClaimCase) {
+    let source_key = CommitKey::new(NonZeroI64::MIN, 0);
+    let Some(request) = case.source.to_request(0) else {
+        return;
+    };
+    let mut request = request.with_source(source_key);
+    check_request(&request);
+    let mut keys = vec![];
+    for op in &case.ops {
+        match op {
+            ClaimOp::Commit { attack, start } => {
+                let Some(track_id) = i64::try_from(keys.len() + 1).ok().and_then(NonZeroI64::new)
+                else {
+                    return;
+                };
+                let track = EnemyTrack::new(vec![attack.clone()]);
+                let commit = FutureMoveCommit::try_create(0, *start, 0).unwrap();
+                let key = CommitKey::new(track_id, *start);
+                let before = request.clone();
+                request.apply_commit_claim(track_id, &track, &commit);
+                check_commit_claim(&before, &request, key, &track, &commit);
+                keys.push(key);
+            }
+            ClaimOp::Release {
+                commit,
+                reopen_from,
+            } => {
+                let Some(key) = keys.get(*commit) else {
+                    continue;
+                };
+                request.release(*key, *reopen_from);
+                check_released(&request, *key);
+            }
+            ClaimOp::ReleaseSource { reopen_from } => {
+                request.release(source_key, *reopen_from);
+                check_released(&request, source_key);
+                assert_eq!(request.source, None);
+                assert!(request.origins.iter().all(Option::is_some));
+            }
+            ClaimOp::ForgetBefore(frame) => {
+                request.forget_before(*frame);
+                assert!(request.request_frames.iter().all(|f| f >= frame));
+            }
+            ClaimOp::ReopenHeld(now) => {
+                request.reopen_held(*now);
+                assert!(
+                    request
+                        .held
+                        .iter()
+                        .all(|(_, reopen_from)| reopen_from > now)
+                );
+            }
+        }
+        check_request(&request);
+    }
+}
+
+// what has to hold for any request.
+fn check_request(request: &ComplementAttackRequest) {
+    let len = request.request_frames.len();
+    assert_eq!(request.taken_requests.len(), len);
+    assert_eq!(request.claimants.len(), len);
+    assert_eq!(request.origins.len(), len);
+    assert!(
+        request.request_frames.is_sorted_by(|a, b| a < b),
+        "request frames out of order or repeated: {:?}",
+        request.request_frames
+    );
+    assert!(
+        request
+            .claimants
+            .iter()
+            .zip(&request.taken_requests)
+            .all(|(claimant, taken)| claimant.is_none() || *taken),
+        "claimed frame is not taken"
+    );
+    let latest_end = request
+        .claim_ends
+        .iter()
+        .map(|(_, end)| *end)
+        .fold(request.source_claim_end, u64::max);
+    assert!(request.claim_end_time() >= latest_end);
+    for (frame, _) in &request.held {
+        let index = request.request_frames.binary_search(frame);
+        assert!(
+            index.is_ok_and(
+                |index| request.taken_requests[index] && request.claimants[index].is_none()
+            ),
+            "held frame {frame} is not declined"
+        );
+    }
+}
+
+// a commit takes the open request frames its active frames land on, only
+// takes frames within its own duration, and adds its request frames past the
+// old claim end. what was taken before keeps its claimant.
+fn check_commit_claim(
+    before: &ComplementAttackRequest,
+    after: &ComplementAttackRequest,
+    key: CommitKey,
+    track: &EnemyTrack,
+    commit: &FutureMoveCommit,
+) {
+    let (start, end) = (commit.get_start_frame(), commit.get_end_frame(track));
+    let old_claim_end = before.claim_end_time();
+    assert_eq!(after.claim_end_time(), old_claim_end.max(end));
+
+    let kept = before.request_frames.len();
+    assert_eq!(after.request_frames[..kept], before.request_frames[..]);
+    let mut added = commit
+        .get_request_frames(track)
+        .filter(|frame| *frame >= old_claim_end)
+        .collect::<Vec<_>>();
+    added.dedup();
+    assert_eq!(after.request_frames[kept..], added[..]);
+    for index in kept..after.request_frames.len() {
+        assert!(!after.taken_requests[index]);
+        assert_eq!(after.origins[index], Some(key));
+    }
+
+    for index in 0..kept {
+        let frame = before.request_frames[index];
+        if before.taken_requests[index] {
+            assert!(after.taken_requests[index]);
+            assert_eq!(after.claimants[index], before.claimants[index]);
+        } else if let Some(claimant) = after.claimants[index] {
+            assert_eq!(claimant, key);
+            assert!(
+                (start..end).contains(&frame),
+                "frame {frame} claimed outside of {start}..{end}"
+            );
+        }
+    }
+    for active in commit
+        .get_active_frames(track)
+        .filter(|frame| *frame < old_claim_end)
+    {
+        let was_open = (0..kept)
+            .any(|index| before.request_frames[index] == active && !before.taken_requests[index]);
+        let claimed = (0..kept).any(|index| {
+            after.request_frames[index] == active && after.claimants[index] == Some(key)
+        });
+        assert!(
+            !was_open || claimed,
+            "active frame {active} did not claim its request frame"
+        );
+    }
+}
+
+// nothing of a released commit is left in the request.
+fn check_released(request: &ComplementAttackRequest, key: CommitKey) {
+    assert!(!request.claimants.contains(&Some(key)));
+    assert!(!request.origins.contains(&Some(key)));
+    assert!(
+        request
+            .claim_ends
+            .iter()
+            .all(|(claimant, _)| *claimant != key)
+    );
+    let latest_end = request
+        .claim_ends
+        .iter()
+        .map(|(_, end)| *end)
+        .fold(request.source_claim_end, u64::max);
+    assert_eq!(request.claim_end_time(), latest_end);
+}
+
+#[cfg(test)]
+mod claim_invariants_tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    pub fn attack() -> impl Strategy<Value = Attack> {
+        (1..48u64)
+            .prop_flat_map(|duration| {
+                (
+                    Just(duration),
+                    prop::collection::vec(0..duration, 0..4),
+                    prop::collection::vec(0..duration, 0..4),
+                )
+            })
+            .prop_map(|(duration, mut active, mut request_frames)| {
+                active.sort_unstable();
+                request_frames.sort_unstable();
+                Attack::new(duration, active, request_frames).unwrap()
+            })
+    }
+
+    fn op() -> impl Strategy<Value = ClaimOp> {
+        prop_oneof![
+            4 => (attack(), 0..64u64).prop_map(|(attack, start)| ClaimOp::Commit { attack, start }),
+            1 => (0..8usize, 0..64u64).prop_map(|(commit, reopen_from)| ClaimOp::Release {
+                commit,
+                reopen_from
+            }),
+            1 => (0..64u64).prop_map(|reopen_from| ClaimOp::ReleaseSource { reopen_from }),
+            1 => (0..64u64).prop_map(ClaimOp::ForgetBefore),
+            1 => (0..64u64).prop_map(ClaimOp::ReopenHeld),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn claims_hold(source in attack(), ops in prop::collection::vec(op(), 0..12)) {
+            check_claims(&ClaimCase { source, ops });
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use crate::solver::snapshot::SnapshotReader;
+use crate::solver::snapshot::SnapshotState;
+use crate::solver::snapshot::SnapshotWriter;
+use std::num::NonZeroI64;
+
+// names a commit across tracks, commits on the same track never start on the
+// same frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommitKey {
+    track_id: NonZeroI64,
+    start_frame: u64,
+}
+
+impl CommitKey {
+    #[must_use]
+    pub fn new(track_id: NonZeroI64, start_frame: u64) -> Self {
+        Self {
+            track_id,
+            start_frame,
+        }
+    }
+    #[must_use]
+    pub fn get_track_id(&self) -> NonZeroI64 {
+        self.track_id
+    }
+    #[must_use]
+    pub fn get_start_frame(&self) -> u64 {
+        self.start_frame
+    }
+}
+
+impl SnapshotState for CommitKey {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.track_id);
+        writer.write(&self.start_frame);
+    }
+    fn read(reader: &mut SnapshotReader) -> Option<Self> {
+        Some(Self {
+            track_id: reader.read()?,
+            start_frame: reader.read()?,
+        })
+    }
+}
@@ -0,0 +1,49 @@
+use super::commit_key::CommitKey;
+
+// one frame of a request, which commit asked for it and which one took it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RequestFrame {
+    frame: u64,
+    origin: Option<CommitKey>,
+    claimant: Option<CommitKey>,
+    taken: bool,
+}
+
+impl RequestFrame {
+    pub(super) fn new(
+        frame: u64,
+        origin: Option<CommitKey>,
+        claimant: Option<CommitKey>,
+        taken: bool,
+    ) -> Self {
+        Self {
+            frame,
+            origin,
+            claimant,
+            taken,
+        }
+    }
+    #[must_use]
+    pub fn get_frame(&self) -> u64 {
+        self.frame
+    }
+    // the commit whose attack asked for the frame, the source of the request
+    // for its own frames. none only for a request without a source.
+    #[must_use]
+    pub fn get_origin(&self) -> Option<CommitKey> {
+        self.origin
+    }
+    #[must_use]
+    pub fn get_claimant(&self) -> Option<CommitKey> {
+        self.claimant
+    }
+    #[must_use]
+    pub fn is_taken(&self) -> bool {
+        self.taken
+    }
+    // taken without anything claiming it, nothing answers it.
+    #[must_use]
+    pub fn is_declined(&self) -> bool {
+        self.taken && self.claimant.is_none()
+    }
+}
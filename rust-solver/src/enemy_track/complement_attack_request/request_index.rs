@@ -0,0 +1,110 @@
+use super::ComplementAttackRequest;
+
+// the open request frames before the claim end of a request, sorted, so a
+// commit can be checked against them with a binary search per active frame
+// instead of walking every request frame. a commit only needs the frames
+// before the claim end to be open, anything after it is not part of the
+// request yet. it grows with the number of open frames, not with how far
+// apart they are.
+//
+// built once per state of the request and shared by every track.
+#[derive(Debug, Default)]
+pub struct RequestIndex {
+    claim_end: u64,
+    open: Vec<u64>,
+}
+
+impl RequestIndex {
+    #[cfg(any(test, feature = "bench"))]
+    #[must_use]
+    pub fn new(request: &ComplementAttackRequest) -> Self {
+        let mut index = Self::default();
+        index.rebuild(request);
+        index
+    }
+    // the same as `new`, but keeps the memory of the old index.
+    pub fn rebuild(&mut self, request: &ComplementAttackRequest) {
+        let claim_end = request.claim_end_time();
+        self.open.clear();
+        self.open.extend(
+            request
+                .request_frames
+                .iter()
+                .zip(&request.taken_requests)
+                .filter(|(frame, taken)| !**taken && **frame < claim_end)
+                .map(|(frame, _)| *frame),
+        );
+        self.claim_end = claim_end;
+    }
+    #[cfg(test)]
+    #[must_use]
+    pub fn is_open(&self, frame: u64) -> bool {
+        self.open.binary_search(&frame).is_ok()
+    }
+    // true if every frame `offsets` after `frame` is open or past the claim
+    // end. the offsets have to be sorted, so every search starts where the
+    // last one ended.
+    #[must_use]
+    pub fn covers(&self, frame: u64, offsets: &[u64]) -> bool {
+        let mut open = self.open.as_slice();
+        offsets
+            .iter()
+            .map(|offset| frame.saturating_add(*offset))
+            .take_while(|target| *target < self.claim_end)
+            .all(|target| {
+                let index = open.partition_point(|open_frame| *open_frame < target);
+                open = &open[index..];
+                open.first() == Some(&target)
+            })
+    }
+}
+
+#[cfg(test)]
+mod request_index_tests {
+    use super::*;
+
+    #[test]
+    fn covers_open_frames() {
+        let mut req = ComplementAttackRequest::new(&[20, 32, 40, 90, 150], 100, 0).unwrap();
+        req.taken_requests[2] = true;
+        let index = RequestIndex::new(&req);
+        assert!(index.is_open(20) && index.is_open(90));
+        assert!(!index.is_open(40) && !index.is_open(21));
+        assert!(!index.is_open(150));
+
+        assert!(index.covers(20, &[12, 70]));
+        assert!(!index.covers(20, &[12, 20]));
+        // frames past the claim end are not checked.
+        assert!(index.covers(32, &[58, 80]));
+        assert!(index.covers(20, &[]));
+    }
+
+    #[test]
+    fn empty_when_all_taken() {
+        let mut req = ComplementAttackRequest::new(&[20], 100, 0).unwrap();
+        req.taken_requests[0] = true;
+        let index = RequestIndex::new(&req);
+        assert!(!index.is_open(20));
+        assert!(index.covers(20, &[90]));
+        assert!(!index.covers(20, &[10]));
+    }
+
+    #[test]
+    fn far_apart_frames() {
+        let far = 1 << 40;
+        let req = ComplementAttackRequest::new(&[20, far, far + 64], far + 100, 0).unwrap();
+        let index = RequestIndex::new(&req);
+        assert!(index.is_open(20) && index.is_open(far) && !index.is_open(far - 1));
+        assert!(index.covers(20, &[far - 20, far + 44]));
+        assert!(!index.covers(20, &[far - 21]));
+        assert!(index.covers(u64::MAX - 1, &[2]));
+    }
+
+    #[test]
+    fn rebuild_forgets_old_frames() {
+        let mut index = RequestIndex::new(&ComplementAttackRequest::new(&[20], 100, 0).unwrap());
+        index.rebuild(&ComplementAttackRequest::new(&[30], 100, 0).unwrap());
+        assert!(!index.is_open(20));
+        assert!(index.is_open(30));
+    }
+}
@@ -1,21 +1,40 @@
 use super::Attack;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct EnemyTrackAttack {
     index: usize,
     attack: Attack,
+    // how far every later active frame is from the first one, the frames a
+    // request needs open for this attack to complement it.
+    followup_offsets: Vec<u64>,
 }
 
 impl EnemyTrackAttack {
     pub fn new(attack: Attack, index: usize) -> Self {
-        Self { index, attack }
+        let mut active = attack.get_active_frames(0);
+        let first = active.next().unwrap_or(0);
+        let mut followup_offsets = active.map(|frame| frame - first).collect::<Vec<_>>();
+        // a second hit on the first frame lands on the same request frame.
+        followup_offsets.retain(|offset| *offset > 0);
+        followup_offsets.dedup();
+        Self {
+            index,
+            attack,
+            followup_offsets,
+        }
     }
     pub fn get_attack(&self) -> &Attack {
         &self.attack
     }
+    pub fn set_index(&mut self, index: usize) {
+        self.index = index;
+    }
     pub fn get_index(&self) -> usize {
         self.index
     }
+    pub fn get_followup_offsets(&self) -> &[u64] {
+        &self.followup_offsets
+    }
     pub fn start_frame_and_index(
         &self,
         request_frame: u64,
@@ -1,8 +1,9 @@
 use super::EnemyTrack;
-use crate::enemy_track::complement_attack_request::ComplementAttackRequest;
-use crate::enemy_track::complement_attack_request::request_offset::RequestOffset;
+use crate::solver::snapshot::SnapshotReader;
+use crate::solver::snapshot::SnapshotState;
+use crate::solver::snapshot::SnapshotWriter;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct FutureMoveCommit {
     start_frame: u64,
     move_index: usize,
@@ -54,44 +55,23 @@ impl FutureMoveCommit {
     pub fn get_end_frame(&self, parent_track: &EnemyTrack) -> u64 {
         self.get_full_duration(parent_track) + self.get_start_frame()
     }
+    pub fn set_index(&mut self, attack_index: usize) {
+        self.move_index = attack_index;
+    }
     pub fn get_index(&self) -> usize {
         self.move_index
     }
-    pub(super) fn active_frame_times(
-        &self,
-        parent_track: &EnemyTrack,
-    ) -> impl Iterator<Item = u64> {
-        parent_track
-            .get_attack(self.get_index())
-            .get_active_frames(self.start_frame)
+}
+
+impl SnapshotState for FutureMoveCommit {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.start_frame);
+        writer.write(&self.move_index);
     }
-    pub(super) fn can_meet_request_followup(
-        &self,
-        parent_track: &EnemyTrack,
-        request: &ComplementAttackRequest,
-        offset: &RequestOffset,
-    ) -> bool {
-        let mut request_iter = request.iter_skip_start(offset);
-        let active_frames_iter = self.active_frame_times(parent_track);
-        for active in active_frames_iter.skip(1) {
-            //if outside of current attack's claim, definitely done.
-            if active >= request.claim_end_time() {
-                return true;
-            }
-            let mut matched = false;
-            for next_request_frame in request_iter.by_ref() {
-                if next_request_frame > active {
-                    break;
-                }
-                if next_request_frame == active {
-                    matched = true;
-                    break;
-                }
-            }
-            if !matched {
-                return false;
-            }
-        }
-        true
+    fn read(reader: &mut SnapshotReader) -> Option<Self> {
+        Some(Self {
+            start_frame: reader.read()?,
+            move_index: reader.read()?,
+        })
     }
 }
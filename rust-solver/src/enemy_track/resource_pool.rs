@@ -0,0 +1,95 @@
+use crate::solver::snapshot::SnapshotReader;
+use crate::solver::snapshot::SnapshotState;
+use crate::solver::snapshot::SnapshotWriter;
+
+// a pool of some resource (stamina, mana, etc.) that attacks of a track spend
+// at their start frame and that regenerates by a fixed amount every frame.
+//
+// the pool only stores the amount at the last frame it was spent from, the
+// amount at any later frame is calculated lazily.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResourcePool {
+    capacity: u64,
+    regen_per_frame: u64,
+    amount: u64,
+    last_update_frame: u64,
+}
+
+impl ResourcePool {
+    #[must_use]
+    pub fn new(capacity: u64, regen_per_frame: u64) -> Self {
+        Self {
+            capacity,
+            regen_per_frame,
+            amount: capacity,
+            last_update_frame: 0,
+        }
+    }
+    #[must_use]
+    pub fn amount_at(&self, frame: u64) -> u64 {
+        let elapsed = frame.saturating_sub(self.last_update_frame);
+        self.regen_per_frame
+            .saturating_mul(elapsed)
+            .saturating_add(self.amount)
+            .min(self.capacity)
+    }
+    #[must_use]
+    pub fn can_afford(&self, frame: u64, cost: u64) -> bool {
+        self.amount_at(frame) >= cost
+    }
+    // spends the cost at the given frame, frames before the last spend are
+    // treated as if they were the last spend frame.
+    pub fn spend_at(&mut self, frame: u64, cost: u64) {
+        let frame = frame.max(self.last_update_frame);
+        self.amount = self.amount_at(frame).saturating_sub(cost);
+        self.last_update_frame = frame;
+    }
+}
+
+impl SnapshotState for ResourcePool {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.capacity);
+        writer.write(&self.regen_per_frame);
+        writer.write(&self.amount);
+        writer.write(&self.last_update_frame);
+    }
+    fn read(reader: &mut SnapshotReader) -> Option<Self> {
+        Some(Self {
+            capacity: reader.read()?,
+            regen_per_frame: reader.read()?,
+            amount: reader.read()?,
+            last_update_frame: reader.read()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod resource_pool_tests {
+    use super::*;
+
+    #[test]
+    fn starts_full() {
+        let pool = ResourcePool::new(10, 1);
+        assert_eq!(pool.amount_at(0), 10);
+        assert!(pool.can_afford(0, 10));
+        assert!(!pool.can_afford(0, 11));
+    }
+
+    #[test]
+    fn regenerates_up_to_capacity() {
+        let mut pool = ResourcePool::new(10, 2);
+        pool.spend_at(5, 8);
+        assert_eq!(pool.amount_at(5), 2);
+        assert_eq!(pool.amount_at(7), 6);
+        assert_eq!(pool.amount_at(100), 10);
+    }
+
+    #[test]
+    fn spend_before_last_update_does_not_regen() {
+        let mut pool = ResourcePool::new(10, 1);
+        pool.spend_at(20, 10);
+        pool.spend_at(10, 0);
+        assert_eq!(pool.amount_at(20), 0);
+        assert_eq!(pool.amount_at(23), 3);
+    }
+}
@@ -0,0 +1,264 @@
+use crate::solver::snapshot::SnapshotReader;
+use crate::solver::snapshot::SnapshotState;
+use crate::solver::snapshot::SnapshotWriter;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PhaseTrigger {
+    // frames since the phase was entered.
+    ElapsedFrames(u64),
+    // attacks finished since the phase was entered.
+    AttacksPerformed(u64),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PhaseTransition {
+    trigger: PhaseTrigger,
+    to: usize,
+}
+
+impl PhaseTransition {
+    #[must_use]
+    pub fn new(trigger: PhaseTrigger, to: usize) -> Self {
+        Self { trigger, to }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Phase {
+    // chance of each attack to be picked relative to the others, by attack index.
+    // attacks with a weight of 0 or without one are disabled in this phase.
+    weights: Vec<u32>,
+    lead_eligible: bool,
+    // the first one to trigger is taken.
+    transitions: Vec<PhaseTransition>,
+}
+
+impl Phase {
+    #[must_use]
+    pub fn new(weights: Vec<u32>, lead_eligible: bool) -> Self {
+        Self {
+            weights,
+            lead_eligible,
+            transitions: vec![],
+        }
+    }
+    #[must_use]
+    pub fn with_transition(mut self, transition: PhaseTransition) -> Self {
+        self.transitions.push(transition);
+        self
+    }
+}
+
+// the phases of a track and which one it is in. only one phase is active at a
+// time, moving to another one is done by `enter` or by a transition of the
+// current phase triggering.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackPhases {
+    phases: Vec<Phase>,
+    current: usize,
+    entered_frame: u64,
+    attacks_performed: u64,
+}
+
+impl TrackPhases {
+    // starts in the first phase, returns none if there are no phases or a
+    // transition goes to a phase that does not exist.
+    #[must_use]
+    pub fn new(phases: Vec<Phase>) -> Option<Self> {
+        let targets_exist = phases
+            .iter()
+            .flat_map(|phase| &phase.transitions)
+            .all(|transition| transition.to < phases.len());
+        if phases.is_empty() || !targets_exist {
+            return None;
+        }
+        Some(Self {
+            phases,
+            current: 0,
+            entered_frame: 0,
+            attacks_performed: 0,
+        })
+    }
+    fn current_phase(&self) -> &Phase {
+        &self.phases[self.current]
+    }
+    #[must_use]
+    pub fn get_current(&self) -> usize {
+        self.current
+    }
+    #[must_use]
+    pub fn weight(&self, attack_index: usize) -> u32 {
+        self.current_phase()
+            .weights
+            .get(attack_index)
+            .copied()
+            .unwrap_or(0)
+    }
+    #[must_use]
+    pub fn is_lead_eligible(&self) -> bool {
+        self.current_phase().lead_eligible
+    }
+    // returns false if the phase does not exist.
+    pub fn enter(&mut self, phase: usize, now: u64) -> bool {
+        if phase >= self.phases.len() {
+            return false;
+        }
+        self.current = phase;
+        self.entered_frame = now;
+        self.attacks_performed = 0;
+        true
+    }
+    pub fn note_attack_performed(&mut self) {
+        self.attacks_performed += 1;
+    }
+    // the phase the current one should move to at this frame, if any.
+    #[must_use]
+    pub fn due_transition(&self, now: u64) -> Option<usize> {
+        let elapsed = now.saturating_sub(self.entered_frame);
+        self.current_phase()
+            .transitions
+            .iter()
+            .find(|transition| match transition.trigger {
+                PhaseTrigger::ElapsedFrames(frames) => elapsed >= frames,
+                PhaseTrigger::AttacksPerformed(count) => self.attacks_performed >= count,
+            })
+            .map(|transition| transition.to)
+    }
+    // a new attack gets a weight of 1 in every phase, the same as every attack
+    // of a track without phases.
+    pub fn push_attack(&mut self, attack_index: usize) {
+        for phase in &mut self.phases {
+            phase.weights.resize(attack_index, 0);
+            phase.weights.push(1);
+        }
+    }
+    // keeps the weights in line with the attacks of the track.
+    pub fn remove_attack(&mut self, attack_index: usize) {
+        for phase in &mut self.phases {
+            if attack_index < phase.weights.len() {
+                phase.weights.remove(attack_index);
+            }
+        }
+    }
+}
+
+impl SnapshotState for PhaseTransition {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        match self.trigger {
+            PhaseTrigger::ElapsedFrames(frames) => {
+                writer.write(&0u8);
+                writer.write(&frames);
+            }
+            PhaseTrigger::AttacksPerformed(count) => {
+                writer.write(&1u8);
+                writer.write(&count);
+            }
+        }
+        writer.write(&self.to);
+    }
+    fn read(reader: &mut SnapshotReader) -> Option<Self> {
+        let trigger = match reader.read::<u8>()? {
+            0 => PhaseTrigger::ElapsedFrames(reader.read()?),
+            1 => PhaseTrigger::AttacksPerformed(reader.read()?),
+            _ => return None,
+        };
+        Some(Self {
+            trigger,
+            to: reader.read()?,
+        })
+    }
+}
+
+impl SnapshotState for Phase {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.weights);
+        writer.write(&self.lead_eligible);
+        writer.write(&self.transitions);
+    }
+    fn read(reader: &mut SnapshotReader) -> Option<Self> {
+        Some(Self {
+            weights: reader.read()?,
+            lead_eligible: reader.read()?,
+            transitions: reader.read()?,
+        })
+    }
+}
+
+impl SnapshotState for TrackPhases {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.phases);
+        writer.write(&self.current);
+        writer.write(&self.entered_frame);
+        writer.write(&self.attacks_performed);
+    }
+    fn read(reader: &mut SnapshotReader) -> Option<Self> {
+        let mut phases = Self::new(reader.read()?)?;
+        let current = reader.read()?;
+        if current >= phases.phases.len() {
+            return None;
+        }
+        phases.current = current;
+        phases.entered_frame = reader.read()?;
+        phases.attacks_performed = reader.read()?;
+        Some(phases)
+    }
+}
+
+#[cfg(test)]
+mod track_phases_tests {
+    use super::*;
+
+    fn two_phases() -> TrackPhases {
+        TrackPhases::new(vec![
+            Phase::new(vec![1, 0, 2], true)
+                .with_transition(PhaseTransition::new(PhaseTrigger::AttacksPerformed(2), 1))
+                .with_transition(PhaseTransition::new(PhaseTrigger::ElapsedFrames(100), 1)),
+            Phase::new(vec![0, 1], false),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn rejects_missing_targets() {
+        assert!(TrackPhases::new(vec![]).is_none());
+        let phase = Phase::new(vec![1], true)
+            .with_transition(PhaseTransition::new(PhaseTrigger::ElapsedFrames(1), 1));
+        assert!(TrackPhases::new(vec![phase]).is_none());
+    }
+
+    #[test]
+    fn transitions_trigger() {
+        let mut phases = two_phases();
+        assert_eq!(phases.weight(2), 2);
+        assert_eq!(phases.weight(3), 0);
+        assert_eq!(phases.due_transition(99), None);
+        assert_eq!(phases.due_transition(100), Some(1));
+
+        phases.note_attack_performed();
+        phases.note_attack_performed();
+        assert_eq!(phases.due_transition(0), Some(1));
+        assert!(phases.enter(1, 50));
+        assert!(!phases.is_lead_eligible());
+        assert_eq!(phases.due_transition(1000), None);
+        assert!(!phases.enter(2, 50));
+    }
+
+    #[test]
+    fn pushed_attack_is_enabled_in_every_phase() {
+        let mut phases = two_phases();
+        phases.push_attack(4);
+        assert_eq!(phases.weight(3), 0);
+        assert_eq!(phases.weight(4), 1);
+        phases.enter(1, 0);
+        assert_eq!(phases.weight(4), 1);
+    }
+
+    #[test]
+    fn remove_attack_shifts_weights() {
+        let mut phases = two_phases();
+        phases.remove_attack(0);
+        assert_eq!(phases.weight(1), 2);
+        phases.enter(1, 0);
+        assert_eq!(phases.weight(0), 1);
+    }
+}
@@ -0,0 +1,119 @@
+use crate::attack::Attack;
+use crate::enemy_track::complement_attack_request::claim_invariants::{
+    ClaimCase, ClaimOp, check_claims,
+};
+use crate::solver::lead_handoff::LeadHandoff;
+use crate::solver::schedule_invariants::{ScheduleCase, check_schedule};
+
+// the fuzz targets in `fuzz/` hand their bytes to these. every input decodes
+// to some case, bytes that run out read as zeros.
+
+/// Checks the request claims of the case the bytes decode to.
+///
+/// # Panics
+///
+/// Panics if the case leaves a request broken.
+pub fn claims(data: &[u8]) {
+    let mut bytes = ByteCursor::new(data);
+    let source = bytes.attack();
+    let mut ops = vec![];
+    while !bytes.is_empty() && ops.len() < 16 {
+        ops.push(match bytes.below(8) {
+            0..4 => ClaimOp::Commit {
+                attack: bytes.attack(),
+                start: bytes.below(64),
+            },
+            4 => ClaimOp::Release {
+                commit: usize::from(bytes.byte() % 8),
+                reopen_from: bytes.below(64),
+            },
+            5 => ClaimOp::ReleaseSource {
+                reopen_from: bytes.below(64),
+            },
+            6 => ClaimOp::ForgetBefore(bytes.below(64)),
+            _ => ClaimOp::ReopenHeld(bytes.below(64)),
+        });
+    }
+    check_claims(&ClaimCase { source, ops });
+}
+
+/// Runs the schedule the bytes decode to.
+///
+/// # Panics
+///
+/// Panics if a track gets commits that overlap.
+pub fn schedule(data: &[u8]) {
+    let mut bytes = ByteCursor::new(data);
+    let seed = u64::from_le_bytes(std::array::from_fn(|_| bytes.byte()));
+    let planning_horizon = bytes.below(120);
+    let filler_slack = bytes.below(40);
+    let lead_handoff = match bytes.below(2) {
+        0 => LeadHandoff::Random,
+        _ => LeadHandoff::LatestEnding,
+    };
+    let lead = bytes.attack();
+    let followers = (0..bytes.below(6))
+        .map(|_| (0..=bytes.below(3)).map(|_| bytes.attack()).collect())
+        .collect();
+    check_schedule(&ScheduleCase {
+        lead,
+        followers,
+        seed,
+        planning_horizon,
+        filler_slack,
+        lead_handoff,
+        frames: 150,
+    });
+}
+
+struct ByteCursor<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> ByteCursor<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+    fn byte(&mut self) -> u8 {
+        let Some((first, rest)) = self.data.split_first() else {
+            return 0;
+        };
+        self.data = rest;
+        *first
+    }
+    fn below(&mut self, end: u64) -> u64 {
+        u64::from(self.byte()) % end
+    }
+    // up to three active and three request frames within the duration.
+    fn attack(&mut self) -> Attack {
+        let duration = 1 + self.below(47);
+        let frames = |cursor: &mut Self| {
+            let mut frames = (0..cursor.below(4))
+                .map(|_| cursor.below(duration))
+                .collect::<Vec<_>>();
+            frames.sort_unstable();
+            frames
+        };
+        let active = frames(self);
+        let request_frames = frames(self);
+        Attack::new(duration, active, request_frames)
+            .expect("frames are sorted and within the duration")
+    }
+}
+
+#[cfg(test)]
+mod fuzz_support_tests {
+    use super::*;
+
+    #[test]
+    fn decodes_any_bytes() {
+        claims(&[]);
+        schedule(&[]);
+        let data = (0..=255u8).rev().collect::<Vec<_>>();
+        claims(&data);
+        schedule(&data);
+    }
+}
@@ -2,9 +2,15 @@ use godot::init::ExtensionLibrary;
 use godot::init::gdextension;
 
 pub(crate) mod attack;
+#[cfg(feature = "bench")]
+pub mod bench_support;
 pub(crate) mod enemy_track;
+#[cfg(feature = "fuzzing")]
+pub mod fuzz_support;
+pub mod replay;
 mod solver;
 pub mod solver_interface;
+mod solver_session;
 
 struct AttackSchedulerExtension;
 
@@ -14,13 +20,27 @@ unsafe impl ExtensionLibrary for AttackSchedulerExtension {}
 #[cfg(test)]
 mod tests {
     use crate::attack::Attack;
+    use crate::attack::zone_use::ZoneUse;
     use crate::enemy_track::EnemyTrack;
+    use crate::enemy_track::complement_attack_request::commit_key::CommitKey;
+    use crate::enemy_track::future_move_commit::FutureMoveCommit;
+    use crate::enemy_track::track_phases::Phase;
+    use crate::enemy_track::track_phases::PhaseTransition;
+    use crate::enemy_track::track_phases::PhaseTrigger;
+    use crate::enemy_track::track_phases::TrackPhases;
     use crate::solver::Solver;
     use crate::solver::SolverRandomState;
+    use crate::solver::difficulty::Difficulty;
+    use crate::solver::difficulty::DifficultyPreset;
+    use crate::solver::lead_handoff::LeadHandoff;
+    use crate::solver::seeded_random::SeededRandom;
+    use crate::solver::solve_budget::SolveBudget;
+    use crate::tests::counting_allocator::allocations_in;
     use crate::tests::default_hasher_random::HashRandom;
-    use std::i64;
     use std::num::NonZeroI64;
+    use std::time::Instant;
 
+    mod counting_allocator;
     mod default_hasher_random;
 
     #[test]
@@ -96,9 +116,806 @@ mod tests {
         for now in 0..120 {
             solver.get_track_mut(lead_key).commit_by_index(2, now, now);
             // solver.try_create_new_request();
-            let _ = solver.solve(&mut random);
+            solver.solve(&mut random);
             solver.tick();
             // dbg!(request.unwrap().claim_end_time());
         }
     }
+
+    pub(crate) fn id(id: i64) -> NonZeroI64 {
+        NonZeroI64::new(id).unwrap()
+    }
+
+    // a solver with track 9 as its lead, which started the attack on frame 0.
+    pub(crate) fn solver_with_lead(attack: Attack) -> Solver {
+        let mut lead = EnemyTrack::new(vec![attack]);
+        assert!(lead.commit_by_index(0, 0, 0));
+        let mut solver = Solver::new();
+        solver.add_track(id(9), lead);
+        solver.change_lead(id(9));
+        solver
+    }
+
+    fn solver_with_followers(follower_count: i64) -> (Solver, NonZeroI64) {
+        let mut lead_track =
+            EnemyTrack::new(vec![Attack::new_expect(80, vec![38], vec![20, 30, 60])]);
+        assert!(lead_track.commit_by_index(0, 2, 0));
+
+        let mut solver = Solver::new();
+        let lead_key = NonZeroI64::new(i64::MAX).unwrap();
+        solver.add_track(lead_key, lead_track);
+        solver.change_lead(lead_key);
+        for count in 0..follower_count {
+            solver.add_track(
+                NonZeroI64::new(count + 1).unwrap(),
+                EnemyTrack::new(vec![Attack::new_expect(10, vec![5], vec![])]),
+            );
+        }
+        (solver, lead_key)
+    }
+
+    // returns the number of followers with a commit, and the number of commits.
+    fn follower_commits(solver: &Solver, follower_count: i64) -> (usize, usize) {
+        (1..=follower_count)
+            .filter_map(|id| solver.find_track(NonZeroI64::new(id).unwrap()))
+            .map(|track| track.future_commits().count())
+            .filter(|count| *count > 0)
+            .fold((0, 0), |(tracks, commits), count| {
+                (tracks + 1, commits + count)
+            })
+    }
+
+    #[test]
+    fn difficulty_limits_complementing_tracks() {
+        let mut random = HashRandom::new(3);
+        let (mut solver, _) = solver_with_followers(5);
+        solver.solve(&mut random);
+        assert_eq!(follower_commits(&solver, 5).1, 3);
+
+        let (mut solver, _) = solver_with_followers(5);
+        let mut difficulty = Difficulty::default();
+        difficulty.set_max_complementing_tracks(Some(1));
+        solver.set_difficulty(difficulty);
+        solver.solve(&mut random);
+        assert_eq!(follower_commits(&solver, 5).0, 1);
+    }
+
+    #[test]
+    fn difficulty_fill_and_spacing() {
+        let mut random = HashRandom::new(3);
+        let (mut solver, _) = solver_with_followers(5);
+        let mut difficulty = Difficulty::default();
+        difficulty.set_fill_percent(0);
+        solver.set_difficulty(difficulty);
+        solver.solve(&mut random);
+        assert_eq!(follower_commits(&solver, 5), (0, 0));
+
+        // frame 32 is 8 frames away from the lead's hit at 40, 22 and 62 are not.
+        let (mut solver, _) = solver_with_followers(5);
+        let mut difficulty = Difficulty::default();
+        difficulty.set_min_hit_spacing(10);
+        solver.set_difficulty(difficulty);
+        solver.solve(&mut random);
+        assert_eq!(follower_commits(&solver, 5).1, 2);
+    }
+
+    #[test]
+    fn plans_lead_until_horizon() {
+        let mut random = HashRandom::new(3);
+        let (mut solver, lead_key) = solver_with_followers(5);
+        solver.solve(&mut random);
+        assert_eq!(solver.get_track(lead_key).future_commits().count(), 1);
+
+        let (mut solver, lead_key) = solver_with_followers(5);
+        solver.set_planning_horizon(300);
+        solver.solve(&mut random);
+        let lead = solver.get_track(lead_key);
+        assert!(lead.first_actionable_frame(0) >= 300);
+        assert!(lead.future_commits().count() >= 4);
+        // followers also answer the attacks that were planned ahead.
+        let last_follower_start = (1..=5)
+            .filter_map(|id| {
+                solver
+                    .get_track(NonZeroI64::new(id).unwrap())
+                    .future_commits()
+                    .last()
+                    .map(FutureMoveCommit::get_start_frame)
+            })
+            .max();
+        assert!(last_follower_start.is_some_and(|start| start > 82));
+    }
+
+    #[test]
+    fn step_budget_carries_over() {
+        let mut random = HashRandom::new(3);
+        let (mut solver, _) = solver_with_followers(5);
+        solver.set_step_budget(1);
+        for commits in 1..=3 {
+            solver.solve(&mut random);
+            assert_eq!(follower_commits(&solver, 5).1, commits);
+        }
+        assert!(solver.solve_within(&mut random, solver.get_step_budget()));
+
+        let (mut solver, _) = solver_with_followers(5);
+        let budget = SolveBudget::unlimited().until(Instant::now());
+        assert!(!solver.solve_within(&mut random, budget));
+        assert_eq!(follower_commits(&solver, 5), (0, 0));
+    }
+
+    // a request frame no track can answer costs a step on every solve, which
+    // must not keep the fillers from being given.
+    #[test]
+    fn fillers_are_given_with_a_small_budget() {
+        let mut random = HashRandom::new(3);
+        let mut solver = solver_with_lead(Attack::new_expect(100, vec![], vec![50]));
+        solver.add_track(
+            id(1),
+            EnemyTrack::new(vec![Attack::new_expect(30, vec![], vec![])]),
+        );
+        solver.set_filler_slack(30);
+        solver.set_step_budget(1);
+        assert!(solver.solve_within(&mut random, solver.get_step_budget()));
+        assert_eq!(follower_commits(&solver, 1), (1, 1));
+        assert!(solver.request_frames().all(|frame| !frame.is_taken()));
+    }
+
+    #[test]
+    fn idle_tracks_get_fillers() {
+        let mut random = HashRandom::new(3);
+        let mut solver = Solver::new();
+        for count in 0..3 {
+            solver.add_track(
+                NonZeroI64::new(count + 1).unwrap(),
+                EnemyTrack::new(vec![
+                    Attack::new_expect(10, vec![5], vec![]),
+                    Attack::new_expect(30, vec![], vec![]),
+                ]),
+            );
+        }
+        solver.solve(&mut random);
+        assert_eq!(follower_commits(&solver, 3), (0, 0));
+
+        solver.set_filler_slack(20);
+        solver.solve(&mut random);
+        assert_eq!(follower_commits(&solver, 3), (0, 0));
+
+        solver.set_filler_slack(30);
+        solver.solve(&mut random);
+        assert_eq!(follower_commits(&solver, 3), (3, 3));
+        // a track busy with a filler is never picked as lead.
+        solver.solve(&mut random);
+        assert_eq!(solver.get_lead(), None);
+    }
+
+    fn step(solver: &mut Solver, random: &mut SeededRandom, lead_key: NonZeroI64) {
+        let now = solver.time_now_frames();
+        solver.update_latest_nonpast();
+        if now.is_multiple_of(50) {
+            solver.get_track_mut(lead_key).commit_by_index(0, now, now);
+        }
+        solver.solve(random);
+        solver.tick();
+    }
+
+    // the buffers grow to the most any track has ever needed first, which
+    // takes a while as it depends on the random picks. with
+    // `LeadHandoff::LatestEnding` followers also ask for answers and outlast
+    // the lead's attack, so the lead is handed to them.
+    #[test]
+    fn steady_state_does_not_allocate() {
+        for (difficulty, handoff) in [
+            (Difficulty::default(), LeadHandoff::Random),
+            (DifficultyPreset::Normal.into(), LeadHandoff::Random),
+            (Difficulty::default(), LeadHandoff::LatestEnding),
+        ] {
+            let mut random = SeededRandom::new(4);
+            let (mut solver, lead_key) = solver_with_followers(20);
+            solver.set_planning_horizon(100);
+            solver.set_difficulty(difficulty);
+            solver.set_lead_handoff(handoff);
+            if handoff == LeadHandoff::LatestEnding {
+                solver.set_planning_horizon(0);
+                for track_id in 1..=20 {
+                    solver
+                        .get_track_mut(NonZeroI64::new(track_id).unwrap())
+                        .push_attack(Attack::new_expect(60, vec![5], vec![40]));
+                }
+            }
+            for _ in 0..20_000 {
+                step(&mut solver, &mut random, lead_key);
+            }
+            let allocations = allocations_in(|| {
+                for _ in 0..300 {
+                    step(&mut solver, &mut random, lead_key);
+                }
+            });
+            assert_eq!(allocations, 0);
+        }
+    }
+
+    #[test]
+    fn restore_and_replay_is_identical() {
+        let mut random = SeededRandom::new(11);
+        let (mut solver, lead_key) = solver_with_followers(20);
+        solver.set_planning_horizon(100);
+        for _ in 0..40 {
+            step(&mut solver, &mut random, lead_key);
+        }
+        let snapshot = solver.snapshot(random);
+        let mut first_run = vec![];
+        for _ in 0..200 {
+            step(&mut solver, &mut random, lead_key);
+            first_run.push(solver.snapshot(random));
+        }
+
+        assert!(solver.restore(&snapshot, &mut random));
+        assert_eq!(solver.snapshot(random), snapshot);
+        for expected in first_run {
+            step(&mut solver, &mut random, lead_key);
+            assert_eq!(solver.snapshot(random), expected);
+        }
+    }
+
+    #[test]
+    fn same_seed_same_schedule() {
+        let run = |reverse_followers: bool| {
+            let mut random = SeededRandom::new(4);
+            let (mut solver, lead_key) = solver_with_followers(0);
+            let mut ids: Vec<i64> = (1..=20).collect();
+            if reverse_followers {
+                ids.reverse();
+            }
+            for id in ids {
+                solver.add_track(
+                    NonZeroI64::new(id).unwrap(),
+                    EnemyTrack::new(vec![Attack::new_expect(10, vec![5], vec![])]),
+                );
+            }
+            let mut schedule = vec![];
+            for _ in 0..150 {
+                step(&mut solver, &mut random, lead_key);
+                schedule.extend(solver.track_ids().flat_map(|id| {
+                    solver
+                        .get_track(id)
+                        .future_commits()
+                        .map(move |commit| (id, commit.get_index(), commit.get_start_frame()))
+                        .collect::<Vec<_>>()
+                }));
+            }
+            schedule
+        };
+        let schedule = run(false);
+        assert!(schedule.iter().any(|(id, _, _)| id.get() < 20));
+        assert_eq!(run(false), schedule);
+        assert_eq!(run(true), schedule);
+    }
+
+    #[test]
+    fn phase_transition_replans() {
+        let mut random = SeededRandom::new(2);
+        let (mut solver, lead_key) = solver_with_followers(0);
+        let follower_key = NonZeroI64::new(1).unwrap();
+        let mut follower = EnemyTrack::new(vec![
+            Attack::new_expect(10, vec![5], vec![]),
+            Attack::new_expect(12, vec![6], vec![]),
+        ]);
+        follower.set_phases(TrackPhases::new(vec![
+            Phase::new(vec![1, 0], true)
+                .with_transition(PhaseTransition::new(PhaseTrigger::ElapsedFrames(30), 1)),
+            Phase::new(vec![0, 1], true),
+        ]));
+        solver.add_track(follower_key, follower);
+
+        // the commit planned in the first phase is dropped once the second starts.
+        let mut commits = vec![];
+        for _ in 0..150 {
+            let now = solver.time_now_frames();
+            step(&mut solver, &mut random, lead_key);
+            let follower = solver.get_track(follower_key);
+            commits.extend(
+                follower
+                    .latest_nonpast_commit()
+                    .map(|commit| (now, commit.get_index())),
+            );
+        }
+        assert_eq!(
+            solver
+                .get_track(follower_key)
+                .get_phases()
+                .unwrap()
+                .get_current(),
+            1
+        );
+        assert!(commits.iter().any(|(now, _)| *now < 30));
+        for (now, index) in commits {
+            assert_eq!(index, usize::from(now >= 30));
+        }
+    }
+
+    #[test]
+    fn phase_change_keeps_what_is_still_enabled() {
+        let mut solver = solver_with_lead(Attack::new_expect(60, vec![], vec![10, 40]));
+        let attacks = || {
+            vec![
+                Attack::new_expect(4, vec![2], vec![]),
+                Attack::new_expect(6, vec![3], vec![]),
+            ]
+        };
+        let mut phased = EnemyTrack::new(attacks());
+        phased.set_phases(TrackPhases::new(vec![
+            Phase::new(vec![1, 0], true),
+            Phase::new(vec![2, 0], true),
+            Phase::new(vec![0, 1], true),
+        ]));
+        solver.add_track(id(1), phased);
+        let mut other = EnemyTrack::new(attacks());
+        other.set_validity(1, false);
+        solver.add_track(id(2), other);
+        solver.solve(&mut SeededRandom::new(1));
+        let commits = |solver: &Solver, track_id| {
+            solver
+                .get_track(id(track_id))
+                .future_commits()
+                .map(|commit| (commit.get_start_frame(), commit.get_index()))
+                .collect::<Vec<_>>()
+        };
+        let (phased_commits, other_commits) = (commits(&solver, 1), commits(&solver, 2));
+        assert_eq!((phased_commits.len(), other_commits.len()), (1, 1));
+
+        // the attack is still enabled, only its weight changed.
+        assert!(solver.enter_phase(id(1), 1));
+        assert_eq!(commits(&solver, 1), phased_commits);
+        assert!(solver.enter_phase(id(1), 2));
+        assert_eq!(commits(&solver, 1), []);
+        assert_eq!(commits(&solver, 2), other_commits);
+        assert_eq!(solver.validate_plan(), []);
+    }
+
+    #[test]
+    fn phases_count_from_when_the_track_is_added() {
+        let mut solver = Solver::new();
+        for _ in 0..5000 {
+            solver.tick();
+        }
+        let mut boss = EnemyTrack::new(vec![Attack::new_expect(10, vec![5], vec![])]);
+        boss.set_phases(TrackPhases::new(vec![
+            Phase::new(vec![1], true)
+                .with_transition(PhaseTransition::new(PhaseTrigger::ElapsedFrames(300), 1)),
+            Phase::new(vec![1], true),
+        ]));
+        let boss_key = NonZeroI64::new(1).unwrap();
+        solver.add_track(boss_key, boss);
+        let phase = |solver: &Solver| {
+            solver
+                .get_track(boss_key)
+                .get_phases()
+                .unwrap()
+                .get_current()
+        };
+        for _ in 0..300 {
+            solver.update_latest_nonpast();
+            assert_eq!(phase(&solver), 0);
+            solver.tick();
+        }
+        solver.update_latest_nonpast();
+        assert_eq!(phase(&solver), 1);
+    }
+
+    #[test]
+    fn disabling_attack_keeps_other_commits() {
+        let mut random = SeededRandom::new(8);
+        let (mut solver, _) = solver_with_followers(0);
+        for id in 1..=3 {
+            solver.add_track(
+                NonZeroI64::new(id).unwrap(),
+                EnemyTrack::new(vec![
+                    Attack::new_expect(10, vec![5], vec![]),
+                    Attack::new_expect(10, vec![5], vec![]),
+                ]),
+            );
+        }
+        let commits = |solver: &Solver| {
+            solver
+                .track_ids()
+                .filter(|id| id.get() <= 3)
+                .flat_map(|id| {
+                    solver
+                        .get_track(id)
+                        .future_commits()
+                        .map(|commit| (id, commit.get_start_frame(), commit.get_index()))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+        solver.solve(&mut random);
+        let before = commits(&solver);
+        assert_eq!(before.len(), 3);
+
+        let (disabled_id, _, disabled_index) = before[0];
+        solver
+            .get_track_mut(disabled_id)
+            .set_validity(disabled_index, false);
+        solver.drop_invalid_commits(disabled_id);
+        assert_eq!(commits(&solver), before[1..]);
+
+        solver.solve(&mut random);
+        let after = commits(&solver);
+        assert_eq!(after.len(), 3);
+        assert!(before[1..].iter().all(|commit| after.contains(commit)));
+        assert!(!after.contains(&before[0]));
+    }
+
+    // the lead has not started the attack the request was made from yet, so
+    // disabling it drops that attack and what answered it, nothing else.
+    #[test]
+    fn disabling_queued_lead_attack_keeps_other_commits() {
+        let mut random = SeededRandom::new(1);
+        let mut lead = EnemyTrack::new(vec![
+            Attack::new_expect(20, vec![], vec![10]),
+            Attack::new_expect(30, vec![], vec![15]),
+        ]);
+        assert!(lead.commit_by_index(0, 2, 0));
+        let mut solver = Solver::new();
+        solver.add_track(id(9), lead);
+        solver.change_lead(id(9));
+        solver.set_planning_horizon(30);
+        for track_id in 1..=2 {
+            solver.add_track(
+                id(track_id),
+                EnemyTrack::new(vec![Attack::new_expect(6, vec![3], vec![])]),
+            );
+        }
+        solver.solve(&mut random);
+        let commits = |solver: &Solver| {
+            solver
+                .track_ids()
+                .flat_map(|track_id| {
+                    solver
+                        .get_track(track_id)
+                        .future_commits()
+                        .map(|commit| (track_id, commit.get_start_frame(), commit.get_index()))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+        let (source, answer, planned) = ((id(9), 2, 0), (id(2), 9, 0), (id(9), 22, 1));
+        let kept = [(id(1), 34, 0), planned];
+        assert_eq!(commits(&solver), [kept[0], answer, source, planned]);
+
+        solver.get_track_mut(id(9)).set_validity(0, false);
+        solver.drop_invalid_commits(id(9));
+        assert_eq!(commits(&solver), kept);
+        assert_eq!(
+            solver
+                .request_frames()
+                .map(|frame| (frame.get_frame(), frame.get_claimant()))
+                .collect::<Vec<_>>(),
+            [(37, Some(CommitKey::new(id(1), 34)))]
+        );
+        assert_eq!(solver.validate_plan(), []);
+    }
+
+    #[test]
+    fn removed_track_frees_its_frames() {
+        let run = |grace| {
+            let mut random = SeededRandom::new(8);
+            let (mut solver, _) = solver_with_followers(4);
+            solver.set_removal_grace(grace);
+            solver.solve(&mut random);
+            let removed = (1..=4)
+                .map(|id| NonZeroI64::new(id).unwrap())
+                .find(|id| solver.get_track(*id).future_commits().count() > 0)
+                .unwrap();
+            solver.remove_track(removed);
+            assert_eq!(follower_commits(&solver, 4).1, 2);
+            solver.solve(&mut random);
+            follower_commits(&solver, 4).1
+        };
+        assert_eq!(run(0), 3);
+        assert_eq!(run(100), 2);
+    }
+
+    #[test]
+    fn removed_lead_takes_its_answers_along() {
+        let mut random = SeededRandom::new(8);
+        let (mut solver, lead_key) = solver_with_followers(3);
+        solver.add_track(
+            id(4),
+            EnemyTrack::new(vec![Attack::new_expect(30, vec![], vec![])]),
+        );
+        solver.set_filler_slack(30);
+        solver.solve(&mut random);
+        assert_eq!(follower_commits(&solver, 4), (4, 4));
+
+        solver.remove_track(lead_key);
+        assert_eq!(follower_commits(&solver, 4), (1, 1));
+        assert_eq!(solver.request_frames().count(), 0);
+        assert_eq!(solver.validate_plan(), []);
+    }
+
+    #[test]
+    fn frames_of_removed_track_are_reassigned_after_grace() {
+        let mut random = SeededRandom::new(5);
+        let mut solver = solver_with_lead(Attack::new_expect(100, vec![], vec![40]));
+        for track_id in [1, 2] {
+            solver.add_track(
+                id(track_id),
+                EnemyTrack::new(vec![Attack::new_expect(4, vec![2], vec![])]),
+            );
+        }
+        solver.set_removal_grace(10);
+        solver.solve(&mut random);
+        let claimant = |solver: &Solver| solver.request_frames().next().unwrap().get_claimant();
+        let removed = claimant(&solver).unwrap().get_track_id();
+        let survivor = if removed == id(1) { id(2) } else { id(1) };
+
+        solver.remove_track(removed);
+        for _ in 0..10 {
+            solver.solve(&mut random);
+            assert_eq!(claimant(&solver), None);
+            solver.tick();
+        }
+        solver.solve(&mut random);
+        assert_eq!(claimant(&solver), Some(CommitKey::new(survivor, 38)));
+    }
+
+    #[test]
+    fn request_frames_know_who_asked_and_answered() {
+        let mut solver = solver_with_lead(Attack::new_expect(40, vec![], vec![10]));
+        // the first follower can only answer the lead, the second only the first.
+        solver.add_track(
+            id(1),
+            EnemyTrack::new(vec![Attack::new_expect(40, vec![0], vec![35])]),
+        );
+        solver.add_track(
+            id(2),
+            EnemyTrack::new(vec![Attack::new_expect(25, vec![20], vec![])]),
+        );
+        solver.solve(&mut SeededRandom::new(4));
+
+        let frames = solver.request_frames().collect::<Vec<_>>();
+        let (lead_key, first_key, second_key) = (
+            CommitKey::new(id(9), 0),
+            CommitKey::new(id(1), 10),
+            CommitKey::new(id(2), 25),
+        );
+        assert_eq!(
+            frames
+                .iter()
+                .map(|frame| (frame.get_frame(), frame.get_origin(), frame.get_claimant()))
+                .collect::<Vec<_>>(),
+            [
+                (10, Some(lead_key), Some(first_key)),
+                (45, Some(first_key), Some(second_key)),
+            ]
+        );
+        assert_eq!(solver.commit_attack(second_key), Some(0));
+
+        // only what the first follower asked for goes with it.
+        solver.remove_track(id(1));
+        let frames = solver.request_frames().collect::<Vec<_>>();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].get_origin(), Some(lead_key));
+        assert!(!frames[0].is_taken());
+        assert_eq!(solver.commit_attack(second_key), None);
+    }
+
+    #[test]
+    fn tracks_stay_out_of_each_others_zones() {
+        let in_zones = |attack: Attack, zones| {
+            let until = attack.get_full_duration();
+            attack
+                .with_zones(vec![ZoneUse::new(zones, 0, until).unwrap()])
+                .unwrap()
+        };
+        let run = |lead_zones, first_zones, second_zones| {
+            let mut solver = solver_with_lead(in_zones(
+                Attack::new_expect(20, vec![], vec![10, 14]),
+                lead_zones,
+            ));
+            for (track_id, zones) in [(1, first_zones), (2, second_zones)] {
+                let attack = in_zones(Attack::new_expect(6, vec![3], vec![]), zones);
+                solver.add_track(id(track_id), EnemyTrack::new(vec![attack]));
+            }
+            solver.solve(&mut SeededRandom::new(2));
+            [1, 2]
+                .map(|track_id| solver.get_track(id(track_id)).future_commits().count())
+                .iter()
+                .sum::<usize>()
+        };
+        assert_eq!(run(0b100, 0b01, 0b10), 2);
+        assert_eq!(run(0b100, 0b01, 0b11), 1);
+        // answering the lead in the zone it takes up is what it asked for.
+        assert_eq!(run(0b11, 0b01, 0b10), 2);
+
+        // the same goes for answering a follower that asked for it.
+        let mut random = SeededRandom::new(2);
+        let mut solver = solver_with_lead(Attack::new_expect(12, vec![], vec![10]));
+        let caller = in_zones(Attack::new_expect(12, vec![3], vec![8]), 0b01);
+        solver.add_track(id(1), EnemyTrack::new(vec![caller]));
+        solver.solve(&mut random);
+        let answer = in_zones(Attack::new_expect(6, vec![3], vec![]), 0b01);
+        solver.add_track(id(2), EnemyTrack::new(vec![answer]));
+        solver.tick();
+        solver.solve(&mut random);
+        let commits = |track_id| {
+            solver
+                .get_track(id(track_id))
+                .future_commits()
+                .map(FutureMoveCommit::get_start_frame)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!((commits(1), commits(2)), (vec![7], vec![12]));
+    }
+
+    #[test]
+    fn lead_goes_to_the_track_that_ends_latest() {
+        let run = |handoff| {
+            let mut random = SeededRandom::new(3);
+            let mut solver = solver_with_lead(Attack::new_expect(12, vec![], vec![10]));
+            solver.set_lead_handoff(handoff);
+            // answers the lead, and asks for an answer of its own.
+            let caller = Attack::new_expect(12, vec![2], vec![6]);
+            solver.add_track(id(1), EnemyTrack::new(vec![caller]));
+            solver.solve(&mut random);
+            solver.add_track(
+                id(2),
+                EnemyTrack::new(vec![Attack::new_expect(4, vec![2], vec![])]),
+            );
+            for _ in 0..12 {
+                solver.tick();
+                solver.update_latest_nonpast();
+                solver.solve(&mut random);
+                assert_eq!(solver.validate_plan(), []);
+            }
+            solver
+        };
+        assert_eq!(run(LeadHandoff::Random).get_lead(), Some(id(9)));
+
+        let solver = run(LeadHandoff::LatestEnding);
+        assert_eq!(solver.get_lead(), Some(id(1)));
+        let frames = solver
+            .request_frames()
+            .map(|frame| (frame.get_frame(), frame.get_origin(), frame.get_claimant()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            frames,
+            [(
+                14,
+                Some(CommitKey::new(id(1), 8)),
+                Some(CommitKey::new(id(2), 12))
+            )]
+        );
+    }
+
+    #[test]
+    fn state_hash_matches_between_runs() {
+        let run = || {
+            let mut random = SeededRandom::new(11);
+            let (mut solver, lead_key) = solver_with_followers(20);
+            solver.set_planning_horizon(100);
+            let mut hashes = vec![];
+            for _ in 0..100 {
+                step(&mut solver, &mut random, lead_key);
+                hashes.push(solver.state_hash(random));
+            }
+            (solver, random, hashes)
+        };
+        let (mut solver, random, hashes) = run();
+        assert_eq!(run().2, hashes);
+        assert_eq!(solver.state_hash(random), hashes[hashes.len() - 1],);
+
+        solver
+            .get_track_mut(NonZeroI64::new(1).unwrap())
+            .set_validity(0, false);
+        assert_ne!(solver.state_hash(random), hashes[hashes.len() - 1]);
+    }
+
+    // big enough that the `parallel` feature splits the tracks across threads.
+    #[test]
+    fn horde_schedule_is_stable() {
+        let run = || {
+            let mut random = SeededRandom::new(3);
+            let (mut solver, lead_key) = solver_with_followers(300);
+            (0..120)
+                .map(|_| {
+                    step(&mut solver, &mut random, lead_key);
+                    solver.state_hash(random)
+                })
+                .collect::<Vec<_>>()
+        };
+        let hashes = run();
+        for _ in 0..3 {
+            assert_eq!(run(), hashes);
+        }
+    }
+
+    #[test]
+    fn restore_undoes_attack_edits() {
+        let mut random = SeededRandom::new(8);
+        let (mut solver, _) = solver_with_followers(2);
+        solver.solve(&mut random);
+        let snapshot = solver.snapshot(random);
+        let (mut unedited, _) = solver_with_followers(2);
+        let mut unedited_random = SeededRandom::new(1);
+        assert!(unedited.restore(&snapshot, &mut unedited_random));
+        let attacks = |solver: &Solver| {
+            solver
+                .get_track(id(1))
+                .attacks()
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        assert!(solver.replace_attack(id(1), 0, Attack::new_expect(12, vec![2], vec![])));
+        solver
+            .get_track_mut(id(1))
+            .push_attack(Attack::new_expect(4, vec![1], vec![]));
+        assert_ne!(
+            solver.state_hash(random),
+            unedited.state_hash(unedited_random)
+        );
+        let mut restored_random = SeededRandom::new(1);
+        assert!(solver.restore(&snapshot, &mut restored_random));
+        assert_eq!(attacks(&solver), attacks(&unedited));
+
+        for _ in 0..40 {
+            for (solver, random) in [
+                (&mut solver, &mut restored_random),
+                (&mut unedited, &mut unedited_random),
+            ] {
+                solver.tick();
+                solver.update_latest_nonpast();
+                solver.solve(random);
+            }
+            assert_eq!(
+                solver.state_hash(restored_random),
+                unedited.state_hash(unedited_random)
+            );
+        }
+    }
+
+    #[test]
+    fn restore_needs_same_tracks() {
+        let random = SeededRandom::new(11);
+        let (solver, _) = solver_with_followers(3);
+        let snapshot = solver.snapshot(random);
+
+        let mut other_random = SeededRandom::new(5);
+        let (mut other, _) = solver_with_followers(4);
+        assert!(!other.restore(&snapshot, &mut other_random));
+        assert_eq!(other_random, SeededRandom::new(5));
+        other.remove_track(NonZeroI64::new(4).unwrap());
+        assert!(other.restore(&snapshot, &mut other_random));
+        assert_eq!(other_random, random);
+    }
+
+    #[test]
+    fn changed_attack_frees_its_frames() {
+        let mut random = SeededRandom::new(8);
+        let mut solver = solver_with_lead(Attack::new_expect(20, vec![], vec![10]));
+        solver.add_track(
+            id(1),
+            EnemyTrack::new(vec![Attack::new_expect(4, vec![2], vec![])]),
+        );
+        solver.solve(&mut random);
+        let starts = |solver: &Solver| {
+            solver
+                .get_track(id(1))
+                .future_commits()
+                .map(FutureMoveCommit::get_start_frame)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(starts(&solver), [8]);
+
+        assert!(solver.replace_attack(id(1), 0, Attack::new_expect(6, vec![4], vec![])));
+        assert_eq!(starts(&solver), []);
+        solver.solve(&mut random);
+        assert_eq!(starts(&solver), [6]);
+
+        assert!(solver.remove_attack(id(1), 0));
+        assert_eq!(starts(&solver), []);
+        // the lead's attack has started, so it stays.
+        assert!(!solver.remove_attack(id(9), 0));
+    }
 }
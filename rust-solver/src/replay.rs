@@ -0,0 +1,110 @@
+use crate::solver_session::SolverSession;
+use crate::solver_session::recording::Recording;
+use crate::solver_session::recording::RecordingEntry;
+use crate::solver_session::session_input::SessionInput;
+use std::fmt;
+
+pub use crate::solver::plan_violation::PlanViolation;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayError {
+    // not a recording, or one from a different version.
+    InvalidFile,
+    // the recorded starting state does not fit the recorded tracks.
+    InvalidInitialState,
+    // the replayed solver started different attacks on this frame.
+    Desync {
+        frame: u64,
+    },
+    // the plan after this frame is not sound, this is the first thing wrong with it.
+    InvalidPlan {
+        frame: u64,
+        violation: PlanViolation,
+    },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::InvalidFile => write!(f, "not a valid recording"),
+            Self::InvalidInitialState => {
+                write!(f, "recorded initial state does not match its tracks")
+            }
+            Self::Desync { frame } => {
+                write!(f, "schedule differs from the recording on frame {frame}")
+            }
+            Self::InvalidPlan { frame, violation } => {
+                write!(f, "plan after frame {frame} is not sound: {violation}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ReplayError {}
+
+/// Runs a recording made with `SolverInterface.stop_recording` without Godot and
+/// checks that every frame starts the same attacks it did when recorded, and
+/// that the plan is sound after every frame.
+///
+/// Returns how many frames were replayed.
+///
+/// # Errors
+///
+/// Returns a [`ReplayError`] if the bytes are not a recording, or on the first
+/// frame where the replayed schedule differs from the recorded one or breaks
+/// the plan.
+pub fn replay(bytes: &[u8]) -> Result<u64, ReplayError> {
+    let recording = Recording::from_bytes(bytes).ok_or(ReplayError::InvalidFile)?;
+    replay_recording(&recording)
+}
+
+pub(crate) fn replay_recording(recording: &Recording) -> Result<u64, ReplayError> {
+    let mut session = SolverSession::new(0);
+    for (id, attacks) in recording.get_tracks() {
+        session.apply(SessionInput::AddTrack {
+            id: *id,
+            attacks: attacks.clone(),
+            resource_pool: None,
+            phases: None,
+        });
+    }
+    let initial_state = SessionInput::RestoreSnapshot(recording.get_initial_state().to_vec());
+    if !session.apply(initial_state) {
+        return Err(ReplayError::InvalidInitialState);
+    }
+
+    let mut frames = 0;
+    for entry in recording.get_entries() {
+        match entry {
+            RecordingEntry::Input(input) => {
+                session.apply(input.clone());
+            }
+            RecordingEntry::Step(started) => {
+                session.step();
+                frames += 1;
+                let frame = session.time_now() - 1;
+                if session.started_this_frame() != *started {
+                    return Err(ReplayError::Desync { frame });
+                }
+                if let Some(violation) = session.get_solver().validate_plan().first() {
+                    return Err(ReplayError::InvalidPlan {
+                        frame,
+                        violation: *violation,
+                    });
+                }
+            }
+        }
+    }
+    Ok(frames)
+}
+
+#[cfg(test)]
+mod replay_tests {
+    use super::*;
+
+    #[test]
+    fn rejects_garbage() {
+        assert_eq!(replay(&[]), Err(ReplayError::InvalidFile));
+        assert_eq!(replay(b"ASRP\x02"), Err(ReplayError::InvalidFile));
+    }
+}
@@ -1,26 +1,115 @@
+use crate::attack::Attack;
 use crate::enemy_track::EnemyTrack;
 use crate::enemy_track::complement_attack_request::ComplementAttackRequest;
+use crate::enemy_track::complement_attack_request::commit_key::CommitKey;
+use crate::enemy_track::complement_attack_request::request_frame::RequestFrame;
 use crate::enemy_track::complement_attack_request::request_offset::RequestOffset;
-use std::collections::HashMap;
+use crate::enemy_track::future_move_commit::FutureMoveCommit;
+use crate::enemy_track::track_phases::TrackPhases;
+use crate::solver::adaptive_difficulty::AdaptiveDifficulty;
+use crate::solver::adaptive_difficulty::PerformanceEvent;
+use crate::solver::difficulty::Difficulty;
+use crate::solver::lead_handoff::LeadHandoff;
+use crate::solver::plan_violation::PlanViolation;
+use crate::solver::solve_budget::SolveBudget;
+use crate::solver::solve_scratch::SolveScratch;
+use crate::solver::track_map::TrackMap;
+use std::cmp::Reverse;
 use std::num::NonZeroI64;
 
-#[derive(Debug)]
+pub mod adaptive_difficulty;
+pub mod difficulty;
+pub mod lead_handoff;
+pub mod plan_violation;
+#[cfg(any(test, feature = "fuzzing"))]
+pub mod schedule_invariants;
+pub mod seeded_random;
+pub mod snapshot;
+pub mod solve_budget;
+mod solve_scratch;
+pub mod track_map;
+pub mod zone_occupancy;
+
+// below this many tracks handing the work to the rayon pool costs more than it
+// saves.
+#[cfg(all(feature = "parallel", not(feature = "nothreads")))]
+const PARALLEL_MIN_TRACKS: usize = 64;
+
+#[derive(Debug, Clone)]
 pub struct Solver {
     lead_track_id: Option<NonZeroI64>,
-    tracks: HashMap<NonZeroI64, EnemyTrack>,
+    tracks: TrackMap,
     lead_request: Option<ComplementAttackRequest>,
     time_now_frames: u64,
+    difficulty: Difficulty,
+    adaptive_difficulty: Option<AdaptiveDifficulty>,
+    // how many frames past now the lead's attacks are planned, 0 only plans
+    // against attacks the lead already has.
+    planning_horizon: u64,
+    // longest filler idle tracks are given, 0 turns fillers off.
+    filler_slack: u64,
+    // frames after a track is removed before what it took can be taken by others.
+    removal_grace: u64,
+    // steps every solve may take, 0 does not limit them.
+    step_budget: u64,
+    lead_handoff: LeadHandoff,
+    scratch: SolveScratch,
 }
 
 impl Solver {
     pub fn new() -> Self {
         Self {
             lead_track_id: None,
-            tracks: HashMap::new(),
+            tracks: TrackMap::default(),
             lead_request: None,
             time_now_frames: 0,
+            difficulty: Difficulty::default(),
+            adaptive_difficulty: None,
+            planning_horizon: 0,
+            filler_slack: 0,
+            removal_grace: 0,
+            step_budget: 0,
+            lead_handoff: LeadHandoff::default(),
+            scratch: SolveScratch::default(),
+        }
+    }
+    pub fn set_filler_slack(&mut self, frames: u64) {
+        self.filler_slack = frames;
+    }
+    pub fn set_removal_grace(&mut self, frames: u64) {
+        self.removal_grace = frames;
+    }
+    pub fn set_step_budget(&mut self, steps: u64) {
+        self.step_budget = steps;
+    }
+    pub fn set_lead_handoff(&mut self, handoff: LeadHandoff) {
+        self.lead_handoff = handoff;
+    }
+    pub fn set_planning_horizon(&mut self, frames: u64) {
+        self.planning_horizon = frames;
+    }
+    // also turns off adaptive difficulty, as it would overwrite this.
+    pub fn set_difficulty(&mut self, difficulty: Difficulty) {
+        self.adaptive_difficulty = None;
+        self.difficulty = difficulty;
+    }
+    pub fn set_adaptive_difficulty(&mut self, adaptive: Option<AdaptiveDifficulty>) {
+        if let Some(adaptive) = &adaptive {
+            self.difficulty = adaptive.current_difficulty();
+        }
+        self.adaptive_difficulty = adaptive;
+    }
+    pub fn get_adaptive_difficulty(&self) -> Option<&AdaptiveDifficulty> {
+        self.adaptive_difficulty.as_ref()
+    }
+    pub fn report_performance(&mut self, event: PerformanceEvent) {
+        if let Some(adaptive) = &mut self.adaptive_difficulty {
+            adaptive.report(event);
         }
     }
+    pub fn get_difficulty(&self) -> &Difficulty {
+        &self.difficulty
+    }
     //swaps in the track at index with lead track, then returns the new index of the track that was swapped
     pub fn change_lead(&mut self, track_id: NonZeroI64) {
         self.lead_track_id = Some(track_id);
@@ -32,20 +121,71 @@ impl Solver {
         self.lead_track_id = None;
         self.lead_request = None;
     }
-    pub fn add_track(&mut self, index: NonZeroI64, track: EnemyTrack) {
+    // the phase the track starts in counts its frames from now, not from the
+    // start of the fight.
+    pub fn add_track(&mut self, index: NonZeroI64, mut track: EnemyTrack) {
+        if let Some(phase) = track.get_phases().map(TrackPhases::get_current) {
+            track.enter_phase(phase, self.time_now_frames());
+        }
         self.tracks.insert(index, track);
     }
+    // the request frames the track took are given back once the removal grace
+    // is over, so they are not taken over right away. the same goes for the
+    // frames of commits that only complemented the track. removing the lead
+    // ends its request, and drops everything that was planned against it.
     pub fn remove_track(&mut self, index: NonZeroI64) {
-        self.tracks.remove(&index);
+        let Some(track) = self.tracks.remove(index) else {
+            return;
+        };
+        let reopen_from = self.time_now_frames().saturating_add(self.removal_grace);
         if self.lead_track_id == Some(index) {
+            let answers = self
+                .lead_request
+                .iter()
+                .flat_map(ComplementAttackRequest::claim_holders)
+                .collect();
+            self.drop_commits(answers, reopen_from);
             self.clear_lead();
+            return;
         }
+        let Some(request) = &mut self.lead_request else {
+            return;
+        };
+        let orphaned = track
+            .future_commits()
+            .flat_map(|commit| {
+                request.release(CommitKey::new(index, commit.get_start_frame()), reopen_from)
+            })
+            .collect();
+        self.drop_commits(orphaned, reopen_from);
+    }
+    // the frames of the lead request, with which commit asked for each one and
+    // which one took it.
+    pub fn request_frames(&self) -> impl Iterator<Item = RequestFrame> + '_ {
+        self.lead_request
+            .iter()
+            .flat_map(ComplementAttackRequest::frames)
+    }
+    // the attack of a planned commit, none once it is no longer planned.
+    pub fn commit_attack(&self, key: CommitKey) -> Option<usize> {
+        self.tracks
+            .get(key.get_track_id())?
+            .future_commits()
+            .find(|commit| commit.get_start_frame() == key.get_start_frame())
+            .map(FutureMoveCommit::get_index)
+    }
+    // in order of their ids.
+    pub fn track_ids(&self) -> impl Iterator<Item = NonZeroI64> {
+        self.tracks.ids()
     }
     pub fn get_track_mut(&mut self, index: NonZeroI64) -> &mut EnemyTrack {
-        self.tracks.get_mut(&index).unwrap()
+        self.tracks.get_mut(index).unwrap()
+    }
+    pub fn find_track(&self, index: NonZeroI64) -> Option<&EnemyTrack> {
+        self.tracks.get(index)
     }
     pub fn get_track(&self, index: NonZeroI64) -> &EnemyTrack {
-        self.tracks.get(&index).unwrap()
+        self.tracks.get(index).unwrap()
     }
     pub fn all_tracks_actionable(&self, start_time: u64) -> bool {
         !self
@@ -53,15 +193,21 @@ impl Solver {
             .iter()
             .any(|(_, value)| !value.is_actionable_now(start_time, self.time_now_frames()))
     }
-    pub fn get_non_actionable_tracks(&self, start_time: u64) -> Vec<&NonZeroI64> {
+    pub fn get_non_actionable_tracks(
+        &self,
+        start_time: u64,
+    ) -> impl Iterator<Item = NonZeroI64> + '_ {
         self.tracks
             .iter()
-            .filter(|(_, value)| !value.is_actionable_now(start_time, self.time_now_frames()))
+            .filter(move |(_, value)| !value.is_actionable_now(start_time, self.time_now_frames()))
             .map(|(index, _)| index)
-            .collect::<Vec<&NonZeroI64>>()
     }
     pub fn tick(&mut self) {
         self.time_now_frames += 1;
+        if let Some(adaptive) = &mut self.adaptive_difficulty {
+            adaptive.tick();
+            self.difficulty = adaptive.current_difficulty();
+        }
     }
     pub fn time_now_frames(&self) -> u64 {
         self.time_now_frames
@@ -73,104 +219,429 @@ impl Solver {
             if req.claim_end_time() > self.time_now_frames {
                 return false;
             }
-            self.lead_request = None;
-            dbg!("lead request cleared!");
+            self.scratch.spare_requests.extend(self.lead_request.take());
         }
         true
     }
+    // tracks the lead may go to, busy with an attack that is not a filler.
+    fn lead_candidates(&self, now: u64) -> impl Iterator<Item = NonZeroI64> + '_ {
+        self.get_non_actionable_tracks(now).filter(|index| {
+            let track = self.get_track(*index);
+            track.is_lead_eligible() && !track.last_commit_is_filler()
+        })
+    }
+    fn latest_ending_candidate(&self, now: u64) -> Option<NonZeroI64> {
+        self.lead_candidates(now)
+            .min_by_key(|index| Reverse(self.get_track(*index).first_actionable_frame(now)))
+    }
+    // with `LeadHandoff::LatestEnding` the lead does not wait for everything
+    // that answered it to end. once its own attack is over, the next request is
+    // made from the last attack of the new lead, and what still holds claims
+    // on the old request keeps the frames of it that fall within its attacks.
+    fn hand_off_lead(&mut self) -> bool {
+        let Some(outgoing) = &self.lead_request else {
+            return false;
+        };
+        if self.lead_handoff != LeadHandoff::LatestEnding || !self.is_valid_lead() {
+            return false;
+        }
+        let now = self.time_now_frames();
+        let Some(lead_id) = self.latest_ending_candidate(now) else {
+            return false;
+        };
+        let Some(lead) = self.tracks.get(lead_id) else {
+            return false;
+        };
+        let mut request = match self.scratch.spare_requests.pop() {
+            Some(mut spare) => {
+                if !lead.last_queued_attack_into_request(lead_id, &mut spare) {
+                    self.scratch.spare_requests.push(spare);
+                    return false;
+                }
+                spare
+            }
+            None => match lead.last_queued_attack_as_request(lead_id) {
+                Some(request) => request,
+                None => return false,
+            },
+        };
+        for key in outgoing.claim_holders() {
+            let track_id = key.get_track_id();
+            let Some(track) = self.tracks.get(track_id) else {
+                continue;
+            };
+            let Some(commit) = track
+                .future_commits()
+                .find(|commit| commit.get_start_frame() == key.get_start_frame())
+            else {
+                continue;
+            };
+            if request.get_source() == Some(key) || commit.get_end_frame(track) <= now {
+                continue;
+            }
+            request.apply_commit_claim(track_id, track, commit);
+            request.note_complementer(track_id);
+        }
+        self.change_lead(lead_id);
+        self.scratch
+            .spare_requests
+            .extend(self.lead_request.replace(request));
+        true
+    }
     fn update_current_request(&mut self, random: &mut impl SolverRandomState) {
-        if !self.try_clear_lead_request() {
+        if self.hand_off_lead() || !self.try_clear_lead_request() {
             return;
         }
         if self.is_valid_lead() {
-            let mut arr = self.get_non_actionable_tracks(self.time_now_frames());
-            if arr.is_empty() {
+            let now = self.time_now_frames();
+            let key = match self.lead_handoff {
+                LeadHandoff::Random => {
+                    let count = self.lead_candidates(now).count();
+                    if count == 0 {
+                        return;
+                    }
+                    let index = random.next_in_range(count);
+                    self.lead_candidates(now).nth(index)
+                }
+                LeadHandoff::LatestEnding => self.latest_ending_candidate(now),
+            };
+            let Some(key) = key else {
                 return;
-            }
-            let index = random.next_in_range(arr.len());
-            let key = arr.swap_remove(index);
-            self.change_lead(*key);
+            };
+            self.change_lead(key);
+        }
+        let Some(lead_id) = self.lead_track_id else {
+            return;
+        };
+        let Some(lead) = self.tracks.get(lead_id) else {
+            return;
+        };
+        let Some(spare) = self.scratch.spare_requests.last_mut() else {
+            self.lead_request = lead.last_queued_attack_as_request(lead_id);
+            return;
+        };
+        if lead.last_queued_attack_into_request(lead_id, spare) {
+            self.lead_request = self.scratch.spare_requests.pop();
         }
-        self.lead_request = self
-            .get_lead_track()
-            .and_then(EnemyTrack::last_queued_attack_as_request);
     }
+    // also moves tracks to their next phase once a transition triggers.
     pub fn update_latest_nonpast(&mut self) {
         let curr_tick = self.time_now_frames();
-        for value in self.tracks.values_mut() {
+        let mut phase_changed = vec![];
+        for (index, value) in self.tracks.iter_mut() {
             value.update_latest_nonpast(curr_tick);
+            if value.update_phase(curr_tick) {
+                phase_changed.push(index);
+            }
+        }
+        for index in phase_changed {
+            self.drop_invalid_commits(index);
+        }
+    }
+    // drops the commits of the track that use attacks the new phase disables,
+    // everything else stays planned.
+    pub fn enter_phase(&mut self, track_id: NonZeroI64, phase: usize) -> bool {
+        let now = self.time_now_frames();
+        let entered = self
+            .tracks
+            .get_mut(track_id)
+            .is_some_and(|track| track.enter_phase(phase, now));
+        if entered {
+            self.drop_invalid_commits(track_id);
         }
+        entered
     }
     fn get_lead_track(&self) -> Option<&EnemyTrack> {
-        self.lead_track_id.as_ref().and_then(|v| self.tracks.get(v))
+        self.lead_track_id.and_then(|v| self.tracks.get(v))
     }
     fn is_valid_lead(&self) -> bool {
         self.get_lead_track()
             .is_none_or(|v| v.is_actionable_now(self.time_now_frames(), self.time_now_frames()))
     }
+    // sorted active frames of every commit that is planned on any track.
+    fn planned_hits(tracks: &TrackMap, hits: &mut Vec<u64>) {
+        hits.clear();
+        hits.extend(tracks.values().flat_map(|track| {
+            track
+                .future_commits()
+                .flat_map(move |commit| commit.get_active_frames(track))
+        }));
+        hits.sort_unstable();
+    }
+    // the options of every track for the current request frame. tracks do not
+    // depend on each other here, so with the `parallel` feature large
+    // encounters split them across the rayon pool. every track writes to its
+    // own position, which keeps the candidates in track order either way.
+    fn fill_track_options(
+        tracks: &TrackMap,
+        request: &ComplementAttackRequest,
+        scratch: &mut SolveScratch,
+        difficulty: Difficulty,
+        now: u64,
+    ) {
+        let open = &scratch.open;
+        let planned_hits = &scratch.planned_hits;
+        let zones = &scratch.zones;
+        let fill = |(index, track): (NonZeroI64, &EnemyTrack),
+                    options: &mut Vec<FutureMoveCommit>| {
+            options.clear();
+            if !difficulty.allows_complementer(request, index) {
+                return;
+            }
+            options.extend(track.possible_future_commits(request, open, now));
+            options.retain(|commit| {
+                // the first active frame is the one the commit answers.
+                let requester = commit
+                    .get_active_frames(track)
+                    .next()
+                    .and_then(|frame| request.requester_of(frame));
+                difficulty.respects_hit_spacing(planned_hits, track, commit)
+                    && zones.allows(index, track, commit, requester)
+            });
+            difficulty.retain_preferred(track, options);
+        };
+        #[cfg(all(feature = "parallel", not(feature = "nothreads")))]
+        if tracks.len() >= PARALLEL_MIN_TRACKS {
+            use rayon::prelude::*;
+            tracks
+                .par_iter()
+                .zip(scratch.track_options.par_iter_mut())
+                .with_min_len(PARALLEL_MIN_TRACKS / 2)
+                .for_each(|(entry, options)| fill(entry, options));
+            return;
+        }
+        for (entry, options) in tracks.iter().zip(&mut scratch.track_options) {
+            fill(entry, options);
+        }
+    }
     fn solve_request(
         &mut self,
         mut request: ComplementAttackRequest,
         random: &mut impl SolverRandomState,
+        budget: &mut SolveBudget,
     ) -> ComplementAttackRequest {
+        let difficulty = self.difficulty;
+        let now = self.time_now_frames();
+        let scratch = &mut self.scratch;
+        scratch.planned_hits.clear();
+        if difficulty.get_min_hit_spacing() > 0 {
+            Self::planned_hits(&self.tracks, &mut scratch.planned_hits);
+        }
+        scratch.zones.rebuild(&self.tracks);
+        scratch
+            .track_options
+            .resize_with(self.tracks.len(), Vec::new);
         let mut request_state = RequestOffset::new_default();
         while let Some(new_offset) = request.next_unclaimed(request_state) {
             request_state = new_offset;
+            if !budget.spend() {
+                break;
+            }
 
-            let mut possible_commits = self
-                .tracks
-                .iter()
-                .map(|(index, track)| {
-                    (
-                        index,
-                        track.possible_future_commits(&request, self.time_now_frames()),
-                    )
-                })
-                .filter(|(_, b)| !b.is_empty())
-                .collect::<Vec<_>>();
+            scratch.open.rebuild(&request);
+            Self::fill_track_options(&self.tracks, &request, scratch, difficulty, now);
+            scratch.candidates.clear();
+            scratch.candidates.extend(
+                self.tracks
+                    .ids()
+                    .zip(&scratch.track_options)
+                    .enumerate()
+                    .filter(|(_, (_, options))| !options.is_empty())
+                    .map(|(position, (index, _))| (index, position)),
+            );
 
-            if possible_commits.is_empty() {
+            if scratch.candidates.is_empty() {
                 break;
             }
-            let index = random.next_in_range(possible_commits.len());
-            let (track_index, mut options) = possible_commits.swap_remove(index);
-            let index = random.next_in_range(options.len());
-            if let Some(track) = self.tracks.get_mut(&track_index.clone()) {
-                let commit = options.swap_remove(index);
-                track.commit(&mut request, commit);
+            if !difficulty.should_fill(random) {
+                request.decline(&request_state);
+                continue;
+            }
+            let index = random.next_in_range(scratch.candidates.len());
+            let (track_id, position) = scratch.candidates[index];
+            if let Some(track) = self.tracks.get_mut(track_id) {
+                let commit = track.take_weighted(&mut scratch.track_options[position], random);
+                if difficulty.get_min_hit_spacing() > 0 {
+                    scratch.planned_hits.extend(commit.get_active_frames(track));
+                    scratch.planned_hits.sort_unstable();
+                }
+                scratch.zones.add(track_id, track, &commit);
+                track.commit(track_id, &mut request, commit);
+                request.note_complementer(track_id);
             }
         }
         request
     }
+    // queues another attack for the lead onto the request if the lead would
+    // run out of attacks before the planning horizon, returns false if it did not.
+    fn extend_lead_plan(
+        &mut self,
+        request: &mut ComplementAttackRequest,
+        random: &mut impl SolverRandomState,
+        budget: &mut SolveBudget,
+    ) -> bool {
+        let now = self.time_now_frames();
+        let horizon_end = now.saturating_add(self.planning_horizon);
+        let difficulty = self.difficulty;
+        let Some(lead_id) = self.lead_track_id else {
+            return false;
+        };
+        self.scratch.zones.rebuild(&self.tracks);
+        let Some(lead) = self.tracks.get_mut(lead_id) else {
+            return false;
+        };
+        if lead.first_actionable_frame(now) >= horizon_end {
+            return false;
+        }
+        let (options, zones) = (&mut self.scratch.options, &self.scratch.zones);
+        options.clear();
+        options.extend(lead.possible_lead_commits(now));
+        options.retain(|commit| zones.allows(lead_id, lead, commit, None));
+        difficulty.retain_preferred(lead, options);
+        if options.is_empty() || !budget.spend() {
+            return false;
+        }
+        let commit = lead.take_weighted(options, random);
+        lead.commit(lead_id, request, commit);
+        true
+    }
+    // everything wrong with what is planned from now on, empty for a sound plan.
+    pub fn validate_plan(&self) -> Vec<PlanViolation> {
+        let now = self.time_now_frames();
+        let mut violations = vec![];
+        for (track_id, track) in self.tracks.iter() {
+            plan_violation::check_track(track_id, track, now, &mut violations);
+        }
+        if let Some(request) = &self.lead_request {
+            plan_violation::check_request(request, &self.tracks, now, &mut violations);
+        }
+        violations
+    }
     pub fn solve(&mut self, random: &mut impl SolverRandomState) {
+        self.solve_within(random, self.get_step_budget());
+    }
+    #[must_use]
+    pub fn get_step_budget(&self) -> SolveBudget {
+        match self.step_budget {
+            0 => SolveBudget::unlimited(),
+            steps => SolveBudget::steps(steps),
+        }
+    }
+    // returns false if the budget ran out, what is left of the request is
+    // planned by the next solve. fillers wait until the request is planned,
+    // so they do not take tracks it could still use.
+    pub fn solve_within(
+        &mut self,
+        random: &mut impl SolverRandomState,
+        mut budget: SolveBudget,
+    ) -> bool {
         self.update_current_request(random);
-        if let Some(request) = self.lead_request.take() {
-            self.lead_request = Some(self.solve_request(request, random));
-        } else {
-            println!("no last queued attack, can not create request and solve");
+        if let Some(mut request) = self.lead_request.take() {
+            request.forget_before(self.time_now_frames());
+            request.reopen_held(self.time_now_frames());
+            let mut request = self.solve_request(request, random, &mut budget);
+            while self.extend_lead_plan(&mut request, random, &mut budget) {
+                request = self.solve_request(request, random, &mut budget);
+            }
+            self.lead_request = Some(request);
+        }
+        if budget.ran_out() {
+            return false;
         }
+        self.schedule_fillers(random);
+        true
     }
-    pub fn reset_non_current(&mut self) {
+    // gives a filler to every track other than the lead that has nothing to do now.
+    fn schedule_fillers(&mut self, random: &mut impl SolverRandomState) {
+        if self.filler_slack == 0 {
+            return;
+        }
         let now = self.time_now_frames();
-        for track in self.tracks.values_mut() {
-            track.reset_non_current(now);
+        let (lead_id, filler_slack) = (self.lead_track_id, self.filler_slack);
+        self.scratch.zones.rebuild(&self.tracks);
+        let (options, zones) = (&mut self.scratch.options, &mut self.scratch.zones);
+        for (index, track) in self.tracks.iter_mut() {
+            if lead_id == Some(index) {
+                continue;
+            }
+            options.clear();
+            options.extend(track.possible_filler_commits(now, filler_slack));
+            options.retain(|commit| zones.allows(index, track, commit, None));
+            if options.is_empty() {
+                continue;
+            }
+            let commit = track.take_weighted(options, random);
+            zones.add(index, track, &commit);
+            track.commit_filler(commit);
         }
-        let mut req = None;
-        for (track, commit) in self
+    }
+    // drops the commits of the track that use disabled attacks and have not
+    // started, along with every commit that was only there to complement them,
+    // and opens the request frames they took again. everything else stays planned.
+    pub fn drop_invalid_commits(&mut self, track_id: NonZeroI64) {
+        let now = self.time_now_frames();
+        let Some(track) = self.tracks.get(track_id) else {
+            return;
+        };
+        let dropped = track
+            .invalid_commits(now)
+            .map(|commit| CommitKey::new(track_id, commit.get_start_frame()))
+            .collect::<Vec<_>>();
+        self.drop_commits(dropped, now);
+    }
+    // the same as `EnemyTrack::replace_attack`, but the request frames taken
+    // by the dropped commits are opened again, and what only complemented them
+    // is dropped as well.
+    pub fn replace_attack(&mut self, track_id: NonZeroI64, index: usize, attack: Attack) -> bool {
+        let now = self.time_now_frames();
+        self.drop_commits_using(track_id, index)
+            && self
+                .get_track_mut(track_id)
+                .replace_attack(index, attack, now)
+    }
+    // the same as `EnemyTrack::remove_attack`, with the dropped commits handled
+    // as in `replace_attack`.
+    pub fn remove_attack(&mut self, track_id: NonZeroI64, index: usize) -> bool {
+        let now = self.time_now_frames();
+        self.drop_commits_using(track_id, index)
+            && self.get_track_mut(track_id).remove_attack(index, now)
+    }
+    // returns false and drops nothing if the attack can not be changed.
+    fn drop_commits_using(&mut self, track_id: NonZeroI64, index: usize) -> bool {
+        let now = self.time_now_frames();
+        let Some(track) = self
             .tracks
-            .values()
-            .filter_map(|track| track.latest_nonpast_commit().map(|commit| (track, commit)))
-            .filter(|(track, commit)| track.commit_valid(commit))
-        {
-            req = match req {
-                None => track.get_commit_as_request(commit),
-                Some(mut req) => {
-                    req.apply_commit_claim(track, commit);
-                    Some(req)
-                }
+            .get(track_id)
+            .filter(|track| track.can_change_attack(index, now))
+        else {
+            return false;
+        };
+        let dropped = track
+            .commits_from_use_of(index)
+            .map(|commit| CommitKey::new(track_id, commit.get_start_frame()))
+            .collect();
+        self.drop_commits(dropped, now);
+        true
+    }
+    // commits that have started already are kept. the request frames they took
+    // are opened again from `reopen_from` on. a request that lost its source
+    // and everything planned against it ends, so the next solve makes a new one.
+    fn drop_commits(&mut self, mut dropped: Vec<CommitKey>, reopen_from: u64) {
+        let now = self.time_now_frames();
+        while let Some(key) = dropped.pop() {
+            if key.get_start_frame() <= now {
+                continue;
             }
+            if let Some(request) = &mut self.lead_request {
+                dropped.extend(request.release(key, reopen_from));
+            }
+            if let Some(track) = self.tracks.get_mut(key.get_track_id()) {
+                track.drop_commit_at(key.get_start_frame());
+            }
+        }
+        if let Some(request) = &mut self.lead_request {
+            request.reopen_held(now);
         }
-        self.lead_request = req;
     }
 }
 
@@ -0,0 +1,169 @@
+use crate::solver::difficulty::Difficulty;
+use crate::solver::snapshot::SnapshotReader;
+use crate::solver::snapshot::SnapshotState;
+use crate::solver::snapshot::SnapshotWriter;
+
+pub const MAX_INTENSITY: u32 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PerformanceEvent {
+    ParrySuccess,
+    ParryMissed,
+    DamageTaken,
+}
+
+impl SnapshotState for PerformanceEvent {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        let tag: u8 = match self {
+            Self::ParrySuccess => 0,
+            Self::ParryMissed => 1,
+            Self::DamageTaken => 2,
+        };
+        writer.write(&tag);
+    }
+    fn read(reader: &mut SnapshotReader) -> Option<Self> {
+        match reader.read::<u8>()? {
+            0 => Some(Self::ParrySuccess),
+            1 => Some(Self::ParryMissed),
+            2 => Some(Self::DamageTaken),
+            _ => None,
+        }
+    }
+}
+
+// moves an intensity value (0 to MAX_INTENSITY) up when the player is doing well
+// and down when they are not, and picks a difficulty between the easiest and the
+// hardest one based on it.
+//
+// events only move the target intensity, the intensity itself follows the target
+// by at most `change_per_frame` every tick so difficulty never jumps.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AdaptiveDifficulty {
+    easiest: Difficulty,
+    hardest: Difficulty,
+    intensity: u32,
+    target_intensity: u32,
+    change_per_frame: u32,
+    success_step: u32,
+    missed_step: u32,
+    damage_step: u32,
+}
+
+impl AdaptiveDifficulty {
+    #[must_use]
+    pub fn new(easiest: Difficulty, hardest: Difficulty) -> Self {
+        Self {
+            easiest,
+            hardest,
+            intensity: MAX_INTENSITY / 2,
+            target_intensity: MAX_INTENSITY / 2,
+            change_per_frame: 1,
+            success_step: 50,
+            missed_step: 50,
+            damage_step: 100,
+        }
+    }
+    pub fn set_steps(&mut self, success: u32, missed: u32, damage: u32) {
+        self.success_step = success;
+        self.missed_step = missed;
+        self.damage_step = damage;
+    }
+    pub fn set_change_per_frame(&mut self, change: u32) {
+        self.change_per_frame = change;
+    }
+    #[must_use]
+    pub fn get_intensity(&self) -> u32 {
+        self.intensity
+    }
+    pub fn report(&mut self, event: PerformanceEvent) {
+        self.target_intensity = match event {
+            PerformanceEvent::ParrySuccess => self
+                .target_intensity
+                .saturating_add(self.success_step)
+                .min(MAX_INTENSITY),
+            PerformanceEvent::ParryMissed => self.target_intensity.saturating_sub(self.missed_step),
+            PerformanceEvent::DamageTaken => self.target_intensity.saturating_sub(self.damage_step),
+        };
+    }
+    pub fn tick(&mut self) {
+        self.intensity = if self.intensity < self.target_intensity {
+            self.intensity
+                .saturating_add(self.change_per_frame)
+                .min(self.target_intensity)
+        } else {
+            self.intensity
+                .saturating_sub(self.change_per_frame)
+                .max(self.target_intensity)
+        };
+    }
+    #[must_use]
+    pub fn current_difficulty(&self) -> Difficulty {
+        Difficulty::interpolate(&self.easiest, &self.hardest, self.intensity, MAX_INTENSITY)
+    }
+}
+
+impl SnapshotState for AdaptiveDifficulty {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.easiest);
+        writer.write(&self.hardest);
+        writer.write(&self.intensity);
+        writer.write(&self.target_intensity);
+        writer.write(&self.change_per_frame);
+        writer.write(&self.success_step);
+        writer.write(&self.missed_step);
+        writer.write(&self.damage_step);
+    }
+    fn read(reader: &mut SnapshotReader) -> Option<Self> {
+        Some(Self {
+            easiest: reader.read()?,
+            hardest: reader.read()?,
+            intensity: reader.read()?,
+            target_intensity: reader.read()?,
+            change_per_frame: reader.read()?,
+            success_step: reader.read()?,
+            missed_step: reader.read()?,
+            damage_step: reader.read()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod adaptive_difficulty_tests {
+    use super::*;
+    use crate::solver::difficulty::DifficultyPreset;
+
+    fn easy_to_hard() -> AdaptiveDifficulty {
+        AdaptiveDifficulty::new(DifficultyPreset::Easy.into(), DifficultyPreset::Hard.into())
+    }
+
+    #[test]
+    fn events_move_target_not_intensity() {
+        let mut adaptive = easy_to_hard();
+        adaptive.report(PerformanceEvent::DamageTaken);
+        assert_eq!(adaptive.get_intensity(), 500);
+        adaptive.tick();
+        assert_eq!(adaptive.get_intensity(), 499);
+        for _ in 0..1000 {
+            adaptive.tick();
+        }
+        assert_eq!(adaptive.get_intensity(), 400);
+    }
+
+    #[test]
+    fn intensity_stays_in_bounds() {
+        let mut adaptive = easy_to_hard();
+        adaptive.set_change_per_frame(MAX_INTENSITY);
+        for _ in 0..100 {
+            adaptive.report(PerformanceEvent::ParrySuccess);
+        }
+        adaptive.tick();
+        assert_eq!(adaptive.get_intensity(), MAX_INTENSITY);
+        assert_eq!(adaptive.current_difficulty(), DifficultyPreset::Hard.into());
+        for _ in 0..100 {
+            adaptive.report(PerformanceEvent::ParryMissed);
+        }
+        adaptive.tick();
+        assert_eq!(adaptive.get_intensity(), 0);
+        assert_eq!(adaptive.current_difficulty(), DifficultyPreset::Easy.into());
+    }
+}
@@ -0,0 +1,275 @@
+use crate::enemy_track::EnemyTrack;
+use crate::enemy_track::complement_attack_request::ComplementAttackRequest;
+use crate::enemy_track::future_move_commit::FutureMoveCommit;
+use crate::solver::SolverRandomState;
+use crate::solver::snapshot::SnapshotReader;
+use crate::solver::snapshot::SnapshotState;
+use crate::solver::snapshot::SnapshotWriter;
+use std::num::NonZeroI64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DifficultyPreset {
+    Easy,
+    Normal,
+    Hard,
+}
+
+impl DifficultyPreset {
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "easy" => Some(Self::Easy),
+            "normal" => Some(Self::Normal),
+            "hard" => Some(Self::Hard),
+            _ => None,
+        }
+    }
+}
+
+// settings that limit how much of a request the solver tries to fill.
+// the default does not limit anything, which is the same as the hard preset.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Difficulty {
+    // chance out of 100 for an unclaimed request frame to be filled,
+    // frames that are not filled are declined and never retried.
+    fill_percent: u32,
+    // minimum number of frames between any two active frames of different commits.
+    min_hit_spacing: u64,
+    max_complementing_tracks: Option<usize>,
+    // attacks with a tier above this are only picked if nothing else fits.
+    max_preferred_tier: Option<u8>,
+}
+
+impl Default for Difficulty {
+    fn default() -> Self {
+        Self::from(DifficultyPreset::Hard)
+    }
+}
+
+impl From<DifficultyPreset> for Difficulty {
+    fn from(preset: DifficultyPreset) -> Self {
+        match preset {
+            DifficultyPreset::Easy => Self {
+                fill_percent: 40,
+                min_hit_spacing: 30,
+                max_complementing_tracks: Some(1),
+                max_preferred_tier: Some(0),
+            },
+            DifficultyPreset::Normal => Self {
+                fill_percent: 75,
+                min_hit_spacing: 12,
+                max_complementing_tracks: Some(3),
+                max_preferred_tier: Some(1),
+            },
+            DifficultyPreset::Hard => Self {
+                fill_percent: 100,
+                min_hit_spacing: 0,
+                max_complementing_tracks: None,
+                max_preferred_tier: None,
+            },
+        }
+    }
+}
+
+fn lerp(from: u64, to: u64, step: u32, steps: u32) -> u64 {
+    let (step, steps) = (u64::from(step.min(steps)), u64::from(steps.max(1)));
+    if to >= from {
+        from + (to - from) * step / steps
+    } else {
+        from - (from - to) * step / steps
+    }
+}
+
+// `None` is unlimited, so there is nothing in between and the closer one is used.
+fn lerp_limit(from: Option<u64>, to: Option<u64>, step: u32, steps: u32) -> Option<u64> {
+    match (from, to) {
+        (Some(from), Some(to)) => Some(lerp(from, to, step, steps)),
+        _ if u64::from(step) * 2 < u64::from(steps) => from,
+        _ => to,
+    }
+}
+
+impl Difficulty {
+    // difficulty `step` out of `steps` of the way from `from` to `to`.
+    #[must_use]
+    pub fn interpolate(from: &Self, to: &Self, step: u32, steps: u32) -> Self {
+        let fill_percent = lerp(
+            u64::from(from.fill_percent),
+            u64::from(to.fill_percent),
+            step,
+            steps,
+        );
+        let max_complementing_tracks = lerp_limit(
+            from.max_complementing_tracks
+                .and_then(|v| u64::try_from(v).ok()),
+            to.max_complementing_tracks
+                .and_then(|v| u64::try_from(v).ok()),
+            step,
+            steps,
+        );
+        let max_preferred_tier = lerp_limit(
+            from.max_preferred_tier.map(u64::from),
+            to.max_preferred_tier.map(u64::from),
+            step,
+            steps,
+        );
+        Self {
+            fill_percent: u32::try_from(fill_percent).unwrap_or(to.fill_percent),
+            min_hit_spacing: lerp(from.min_hit_spacing, to.min_hit_spacing, step, steps),
+            max_complementing_tracks: max_complementing_tracks
+                .and_then(|v| usize::try_from(v).ok()),
+            max_preferred_tier: max_preferred_tier.and_then(|v| u8::try_from(v).ok()),
+        }
+    }
+    pub fn set_fill_percent(&mut self, fill_percent: u32) {
+        self.fill_percent = fill_percent.min(100);
+    }
+    pub fn set_min_hit_spacing(&mut self, frames: u64) {
+        self.min_hit_spacing = frames;
+    }
+    pub fn set_max_complementing_tracks(&mut self, max: Option<usize>) {
+        self.max_complementing_tracks = max;
+    }
+    pub fn set_max_preferred_tier(&mut self, tier: Option<u8>) {
+        self.max_preferred_tier = tier;
+    }
+    #[must_use]
+    pub fn get_min_hit_spacing(&self) -> u64 {
+        self.min_hit_spacing
+    }
+    // does not use the random state at all when everything is filled, so that
+    // the default difficulty picks the same moves as it did without one.
+    pub fn should_fill(&self, random: &mut impl SolverRandomState) -> bool {
+        self.fill_percent >= 100 || random.next_in_range(100) < self.fill_percent as usize
+    }
+    #[must_use]
+    pub fn allows_complementer(
+        &self,
+        request: &ComplementAttackRequest,
+        track_id: NonZeroI64,
+    ) -> bool {
+        self.max_complementing_tracks.is_none_or(|max| {
+            request.is_complementer(track_id) || request.complementer_count() < max
+        })
+    }
+    // `planned_hits` must be sorted.
+    #[must_use]
+    pub fn respects_hit_spacing(
+        &self,
+        planned_hits: &[u64],
+        track: &EnemyTrack,
+        commit: &FutureMoveCommit,
+    ) -> bool {
+        if self.min_hit_spacing == 0 {
+            return true;
+        }
+        commit.get_active_frames(track).all(|hit| {
+            let next = planned_hits.partition_point(|planned| *planned < hit);
+            let after_ok = planned_hits
+                .get(next)
+                .is_none_or(|planned| planned - hit >= self.min_hit_spacing);
+            let before_ok = next
+                .checked_sub(1)
+                .and_then(|prev| planned_hits.get(prev))
+                .is_none_or(|planned| hit - planned >= self.min_hit_spacing);
+            after_ok && before_ok
+        })
+    }
+    // keeps only the options that use preferred attacks, unless there are none.
+    pub fn retain_preferred(&self, track: &EnemyTrack, options: &mut Vec<FutureMoveCommit>) {
+        let Some(max_tier) = self.max_preferred_tier else {
+            return;
+        };
+        let is_preferred =
+            |commit: &FutureMoveCommit| track.get_attack(commit.get_index()).get_tier() <= max_tier;
+        if options.iter().any(is_preferred) {
+            options.retain(is_preferred);
+        }
+    }
+}
+
+impl SnapshotState for Difficulty {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.fill_percent);
+        writer.write(&self.min_hit_spacing);
+        writer.write(&self.max_complementing_tracks);
+        writer.write(&self.max_preferred_tier);
+    }
+    fn read(reader: &mut SnapshotReader) -> Option<Self> {
+        Some(Self {
+            fill_percent: reader.read()?,
+            min_hit_spacing: reader.read()?,
+            max_complementing_tracks: reader.read()?,
+            max_preferred_tier: reader.read()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod difficulty_tests {
+    use super::*;
+    use crate::attack::Attack;
+
+    #[test]
+    fn preset_names() {
+        assert_eq!(
+            DifficultyPreset::from_name("easy"),
+            Some(DifficultyPreset::Easy)
+        );
+        assert_eq!(
+            DifficultyPreset::from_name("hard"),
+            Some(DifficultyPreset::Hard)
+        );
+        assert_eq!(DifficultyPreset::from_name("nightmare"), None);
+        assert_eq!(Difficulty::default(), DifficultyPreset::Hard.into());
+    }
+
+    #[test]
+    fn interpolate_between_presets() {
+        let easy = Difficulty::from(DifficultyPreset::Easy);
+        let hard = Difficulty::from(DifficultyPreset::Hard);
+        assert_eq!(Difficulty::interpolate(&easy, &hard, 0, 10), easy);
+        assert_eq!(Difficulty::interpolate(&easy, &hard, 10, 10), hard);
+
+        let half = Difficulty::interpolate(&easy, &hard, 5, 10);
+        assert_eq!(half.fill_percent, 70);
+        assert_eq!(half.min_hit_spacing, 15);
+        assert_eq!(half.max_complementing_tracks, None);
+        let below_half = Difficulty::interpolate(&easy, &hard, 4, 10);
+        assert_eq!(below_half.max_complementing_tracks, Some(1));
+    }
+
+    #[test]
+    fn hit_spacing() {
+        let mut track = EnemyTrack::new(vec![Attack::new_expect(30, vec![10, 20], vec![])]);
+        assert!(track.commit_by_index(0, 0, 0));
+        let commit = track.latest_nonpast_commit().unwrap();
+
+        let mut difficulty = Difficulty::default();
+        difficulty.set_min_hit_spacing(5);
+        assert!(difficulty.respects_hit_spacing(&[4, 15, 25], &track, commit));
+        assert!(!difficulty.respects_hit_spacing(&[4, 16, 25], &track, commit));
+        assert!(!difficulty.respects_hit_spacing(&[10], &track, commit));
+    }
+
+    #[test]
+    fn prefers_low_tier() {
+        let track = EnemyTrack::new(vec![
+            Attack::new_expect(30, vec![10], vec![]).with_tier(2),
+            Attack::new_expect(30, vec![10], vec![]),
+        ]);
+        let difficulty = Difficulty::from(DifficultyPreset::Normal);
+        let mut options: Vec<FutureMoveCommit> = (0..2)
+            .filter_map(|index| FutureMoveCommit::try_create(index, 0, 0))
+            .collect();
+        difficulty.retain_preferred(&track, &mut options);
+        assert_eq!(options.len(), 1);
+        assert_eq!(options[0].get_index(), 1);
+
+        // falls back to everything if there is no preferred option.
+        let mut options: Vec<FutureMoveCommit> =
+            FutureMoveCommit::try_create(0, 0, 0).into_iter().collect();
+        difficulty.retain_preferred(&track, &mut options);
+        assert_eq!(options.len(), 1);
+    }
+}
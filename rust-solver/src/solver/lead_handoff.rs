@@ -0,0 +1,45 @@
+use crate::solver::snapshot::SnapshotReader;
+use crate::solver::snapshot::SnapshotState;
+use crate::solver::snapshot::SnapshotWriter;
+
+// which track becomes the lead once the attack of the current one is over and
+// it has nothing else queued. only tracks that are still busy with an attack
+// that is not a filler are picked, the new lead's last attack is what the next
+// request is made from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LeadHandoff {
+    #[default]
+    Random,
+    // the track whose planned attacks end the latest, the one with the lowest
+    // id if several end on the same frame. as that is usually the one that
+    // answered the lead last, enemies take turns calling and answering.
+    LatestEnding,
+}
+
+impl LeadHandoff {
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "random" => Some(Self::Random),
+            "latest_ending" => Some(Self::LatestEnding),
+            _ => None,
+        }
+    }
+}
+
+impl SnapshotState for LeadHandoff {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        let tag: u8 = match self {
+            Self::Random => 0,
+            Self::LatestEnding => 1,
+        };
+        writer.write(&tag);
+    }
+    fn read(reader: &mut SnapshotReader) -> Option<Self> {
+        match reader.read::<u8>()? {
+            0 => Some(Self::Random),
+            1 => Some(Self::LatestEnding),
+            _ => None,
+        }
+    }
+}
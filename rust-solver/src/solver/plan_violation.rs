@@ -0,0 +1,269 @@
+use crate::enemy_track::EnemyTrack;
+use crate::enemy_track::complement_attack_request::ComplementAttackRequest;
+use crate::enemy_track::complement_attack_request::commit_key::CommitKey;
+use crate::solver::track_map::TrackMap;
+use std::fmt;
+use std::num::NonZeroI64;
+
+// something wrong with what the solver has planned, found by `Solver::validate_plan`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanViolation {
+    // the commit starts before the one before it on its track has ended.
+    Overlap {
+        commit: CommitKey,
+        previous_end: u64,
+    },
+    // the commit is not the one running, but starts on a frame that has passed.
+    StartsBeforeActionable {
+        commit: CommitKey,
+        actionable_frame: u64,
+    },
+    // the commit has not started and uses an attack that is disabled.
+    InvalidAttack {
+        commit: CommitKey,
+        attack_index: usize,
+    },
+    // the request frame is claimed by a commit that is no longer planned, or
+    // that does not run on it. commits take every frame their attack runs over.
+    UnmatchedClaim {
+        frame: u64,
+        claimant: CommitKey,
+    },
+    // the commit hits on a request frame that nothing has claimed.
+    UnclaimedHit {
+        frame: u64,
+        commit: CommitKey,
+    },
+}
+
+impl fmt::Display for PlanViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Overlap {
+                commit,
+                previous_end,
+            } => write!(
+                f,
+                "{} starts before the commit before it ends on frame {previous_end}",
+                Key(commit)
+            ),
+            Self::StartsBeforeActionable {
+                commit,
+                actionable_frame,
+            } => write!(
+                f,
+                "{} starts before frame {actionable_frame}, which has passed without it",
+                Key(commit)
+            ),
+            Self::InvalidAttack {
+                commit,
+                attack_index,
+            } => write!(f, "{} uses disabled attack {attack_index}", Key(commit)),
+            Self::UnmatchedClaim { frame, claimant } => write!(
+                f,
+                "request frame {frame} is claimed by {}, which does not run on it",
+                Key(claimant)
+            ),
+            Self::UnclaimedHit { frame, commit } => write!(
+                f,
+                "{} hits on request frame {frame}, which nothing claimed",
+                Key(commit)
+            ),
+        }
+    }
+}
+
+struct Key<'a>(&'a CommitKey);
+
+impl fmt::Display for Key<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "commit of track {} on frame {}",
+            self.0.get_track_id(),
+            self.0.get_start_frame()
+        )
+    }
+}
+
+// commits of a track run one after the other in the order of its stack. the
+// first one may have started already, the others have to start from now on.
+pub fn check_track(
+    track_id: NonZeroI64,
+    track: &EnemyTrack,
+    now: u64,
+    violations: &mut Vec<PlanViolation>,
+) {
+    let mut previous_end = None;
+    for commit in track.future_commits() {
+        let key = CommitKey::new(track_id, commit.get_start_frame());
+        match previous_end {
+            Some(end) if commit.get_start_frame() < end => {
+                violations.push(PlanViolation::Overlap {
+                    commit: key,
+                    previous_end: end,
+                });
+            }
+            Some(_) if commit.get_start_frame() < now => {
+                violations.push(PlanViolation::StartsBeforeActionable {
+                    commit: key,
+                    actionable_frame: now,
+                });
+            }
+            _ => {}
+        }
+        previous_end = Some(commit.get_end_frame(track));
+    }
+    violations.extend(
+        track
+            .invalid_commits(now)
+            .map(|commit| PlanViolation::InvalidAttack {
+                commit: CommitKey::new(track_id, commit.get_start_frame()),
+                attack_index: commit.get_index(),
+            }),
+    );
+}
+
+// claims of the request frames that are still ahead have to match the active
+// frames of what is planned, the past ones do not matter anymore.
+pub fn check_request(
+    request: &ComplementAttackRequest,
+    tracks: &TrackMap,
+    now: u64,
+    violations: &mut Vec<PlanViolation>,
+) {
+    // where every planned commit runs, sorted to look claimants up quickly.
+    let mut runs = tracks
+        .iter()
+        .flat_map(|(track_id, track)| {
+            track.future_commits().map(move |commit| {
+                let start_frame = commit.get_start_frame();
+                ((track_id, start_frame), commit.get_end_frame(track))
+            })
+        })
+        .collect::<Vec<_>>();
+    runs.sort_unstable();
+    let runs_on = |claimant: CommitKey, frame: u64| {
+        let key = (claimant.get_track_id(), claimant.get_start_frame());
+        runs.binary_search_by_key(&key, |(key, _)| *key)
+            .is_ok_and(|index| (key.1..runs[index].1).contains(&frame))
+    };
+    let mut open = vec![];
+    for request_frame in request.frames().filter(|frame| frame.get_frame() >= now) {
+        let frame = request_frame.get_frame();
+        if let Some(claimant) = request_frame.get_claimant() {
+            if !runs_on(claimant, frame) {
+                violations.push(PlanViolation::UnmatchedClaim { frame, claimant });
+            }
+        } else if !request_frame.is_taken() {
+            open.push(frame);
+        }
+    }
+    for (track_id, track) in tracks.iter() {
+        for commit in track.future_commits() {
+            let key = CommitKey::new(track_id, commit.get_start_frame());
+            // fillers never answer requests, and the source makes them.
+            if request.get_source() == Some(key) || track.get_attack(commit.get_index()).is_filler()
+            {
+                continue;
+            }
+            // an attack may ask for a complement on a frame it hits on itself.
+            violations.extend(
+                commit
+                    .get_active_frames(track)
+                    .filter(|frame| open.binary_search(frame).is_ok())
+                    .filter(|frame| !commit.get_request_frames(track).any(|own| own == *frame))
+                    .map(|frame| PlanViolation::UnclaimedHit { frame, commit: key }),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod plan_violation_tests {
+    use super::*;
+    use crate::attack::Attack;
+    use crate::enemy_track::future_move_commit::FutureMoveCommit;
+    use crate::solver::Solver;
+    use crate::solver::seeded_random::SeededRandom;
+    use crate::tests::id;
+    use crate::tests::solver_with_lead;
+
+    // commits without checking anything, the way only a broken solver would.
+    fn force_commit(track: &mut EnemyTrack, track_id: NonZeroI64, start_frame: u64) {
+        let mut unrelated = ComplementAttackRequest::new(&[1000], 1001, 0).unwrap();
+        let commit = FutureMoveCommit::try_create(0, start_frame, 0).unwrap();
+        track.commit(track_id, &mut unrelated, commit);
+    }
+
+    #[test]
+    fn finds_broken_tracks() {
+        let mut solver = Solver::new();
+        let mut track = EnemyTrack::new(vec![Attack::new_expect(4, vec![2], vec![])]);
+        for start_frame in [0, 2, 6, 20] {
+            force_commit(&mut track, id(1), start_frame);
+        }
+        track.set_validity(0, false);
+        solver.add_track(id(1), track);
+        for _ in 0..8 {
+            solver.tick();
+        }
+        assert_eq!(
+            solver.validate_plan(),
+            [
+                PlanViolation::Overlap {
+                    commit: CommitKey::new(id(1), 2),
+                    previous_end: 4,
+                },
+                PlanViolation::StartsBeforeActionable {
+                    commit: CommitKey::new(id(1), 6),
+                    actionable_frame: 8,
+                },
+                PlanViolation::InvalidAttack {
+                    commit: CommitKey::new(id(1), 20),
+                    attack_index: 0,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_claims_without_commits() {
+        let mut solver = solver_with_lead(Attack::new_expect(20, vec![], vec![10]));
+        solver.add_track(
+            id(1),
+            EnemyTrack::new(vec![Attack::new_expect(4, vec![2], vec![])]),
+        );
+        solver.solve(&mut SeededRandom::new(1));
+        assert_eq!(solver.validate_plan(), []);
+
+        solver.get_track_mut(id(1)).drop_commit_at(8);
+        assert_eq!(
+            solver.validate_plan(),
+            [PlanViolation::UnmatchedClaim {
+                frame: 10,
+                claimant: CommitKey::new(id(1), 8),
+            }]
+        );
+    }
+
+    #[test]
+    fn finds_unclaimed_hits() {
+        let mut solver = solver_with_lead(Attack::new_expect(20, vec![], vec![10]));
+        let mut follower = EnemyTrack::new(vec![Attack::new_expect(4, vec![2], vec![])]);
+        force_commit(&mut follower, id(1), 8);
+        solver.add_track(id(1), follower);
+        solver.solve(&mut SeededRandom::new(1));
+        assert_eq!(
+            solver.validate_plan(),
+            [PlanViolation::UnclaimedHit {
+                frame: 10,
+                commit: CommitKey::new(id(1), 8),
+            }]
+        );
+        assert_eq!(
+            solver.validate_plan()[0].to_string(),
+            "commit of track 1 on frame 8 hits on request frame 10, which nothing claimed"
+        );
+    }
+}
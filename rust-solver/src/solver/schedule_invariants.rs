@@ -0,0 +1,117 @@
+use crate::attack::Attack;
+use crate::enemy_track::EnemyTrack;
+use crate::solver::Solver;
+use crate::solver::lead_handoff::LeadHandoff;
+use crate::solver::seeded_random::SeededRandom;
+use std::num::NonZeroI64;
+
+// tracks whose attacks request each other chain without an end, so every solve
+// gets a step budget the way a game that uses such tracks would.
+const STEPS_PER_SOLVE: u64 = 64;
+
+// a lead that starts its first attack whenever every track can act, the way a
+// session commits moves, and followers that answer it and may take over the lead.
+#[derive(Debug, Clone)]
+pub struct ScheduleCase {
+    pub lead: Attack,
+    pub followers: Vec<Vec<Attack>>,
+    pub seed: u64,
+    pub planning_horizon: u64,
+    pub filler_slack: u64,
+    pub lead_handoff: LeadHandoff,
+    pub frames: u64,
+}
+
+/// Runs the case and validates the plan after every solve.
+///
+/// # Panics
+///
+/// Panics on the first frame with a plan that is not sound.
+pub fn check_schedule(case: &ScheduleCase) {
+    let mut solver = Solver::new();
+    let lead_id = NonZeroI64::MAX;
+    solver.add_track(lead_id, EnemyTrack::new(vec![case.lead.clone()]));
+    solver.change_lead(lead_id);
+    for (id, attacks) in (1..).filter_map(NonZeroI64::new).zip(&case.followers) {
+        solver.add_track(id, EnemyTrack::new(attacks.clone()));
+    }
+    solver.set_planning_horizon(case.planning_horizon);
+    solver.set_filler_slack(case.filler_slack);
+    solver.set_step_budget(STEPS_PER_SOLVE);
+    solver.set_lead_handoff(case.lead_handoff);
+    let mut random = SeededRandom::new(case.seed);
+    for _ in 0..case.frames {
+        let now = solver.time_now_frames();
+        solver.update_latest_nonpast();
+        if solver.all_tracks_actionable(now) {
+            let lead_id = solver.get_lead().unwrap_or(lead_id);
+            solver.get_track_mut(lead_id).commit_by_index(0, now, now);
+        }
+        solver.solve(&mut random);
+        let violations = solver.validate_plan();
+        assert!(violations.is_empty(), "frame {now}: {violations:?}");
+        solver.tick();
+    }
+}
+
+#[cfg(test)]
+mod schedule_invariants_tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn attack() -> impl Strategy<Value = Attack> {
+        (1..40u64)
+            .prop_flat_map(|duration| {
+                (
+                    Just(duration),
+                    prop::collection::vec(0..duration, 0..3),
+                    prop::collection::vec(0..duration, 0..3),
+                )
+            })
+            .prop_map(|(duration, mut active, mut request_frames)| {
+                active.sort_unstable();
+                request_frames.sort_unstable();
+                Attack::new(duration, active, request_frames).unwrap()
+            })
+    }
+
+    // the lead and the follower request each other on every attack.
+    #[test]
+    fn endless_chain_stays_within_budget() {
+        let attack = |duration, active, request_frames| {
+            Attack::new(duration, active, request_frames).unwrap()
+        };
+        check_schedule(&ScheduleCase {
+            lead: attack(9, vec![1], vec![6]),
+            followers: vec![vec![attack(15, vec![1, 12], vec![11])]],
+            seed: 3,
+            planning_horizon: 0,
+            filler_slack: 0,
+            lead_handoff: LeadHandoff::Random,
+            frames: 150,
+        });
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+        #[test]
+        fn tracks_never_overlap(
+            lead in attack(),
+            followers in prop::collection::vec(prop::collection::vec(attack(), 1..4), 0..6),
+            seed in any::<u64>(),
+            planning_horizon in prop_oneof![Just(0u64), 0..120u64],
+            filler_slack in prop_oneof![Just(0u64), 0..40u64],
+            lead_handoff in prop_oneof![Just(LeadHandoff::Random), Just(LeadHandoff::LatestEnding)],
+        ) {
+            check_schedule(&ScheduleCase {
+                lead,
+                followers,
+                seed,
+                planning_horizon,
+                filler_slack,
+                lead_handoff,
+                frames: 150,
+            });
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use crate::solver::SolverRandomState;
+
+// splitmix64, small enough that its whole state can be saved in a snapshot and
+// gives the same numbers on every machine for the same seed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeededRandom {
+    state: u64,
+}
+
+impl SeededRandom {
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+    #[must_use]
+    pub fn get_state(self) -> u64 {
+        self.state
+    }
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+impl SolverRandomState for SeededRandom {
+    #[allow(clippy::cast_possible_truncation)]
+    fn next_in_range(&mut self, max: usize) -> usize {
+        let v = u128::from(self.next_u64()) * max as u128;
+        (v >> 64) as usize
+    }
+}
+
+#[cfg(test)]
+mod seeded_random_tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_values() {
+        let mut rand1 = SeededRandom::new(7);
+        let mut rand2 = SeededRandom::new(7);
+        for _ in 0..1000 {
+            let random_v = rand1.next_in_range(5);
+            assert!(random_v < 5);
+            assert_eq!(random_v, rand2.next_in_range(5));
+        }
+    }
+
+    #[test]
+    fn resume_from_state() {
+        let mut rand1 = SeededRandom::new(7);
+        rand1.next_in_range(5);
+        let mut rand2 = SeededRandom::new(rand1.get_state());
+        assert_eq!(rand1.next_in_range(1000), rand2.next_in_range(1000));
+    }
+}
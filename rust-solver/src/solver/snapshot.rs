@@ -0,0 +1,381 @@
+use crate::enemy_track::EnemyTrack;
+use crate::enemy_track::EnemyTrackState;
+use crate::enemy_track::complement_attack_request::ComplementAttackRequest;
+use crate::solver::Solver;
+use crate::solver::adaptive_difficulty::AdaptiveDifficulty;
+use crate::solver::difficulty::Difficulty;
+use crate::solver::lead_handoff::LeadHandoff;
+use crate::solver::seeded_random::SeededRandom;
+use std::num::NonZeroI64;
+
+// everything in a solver that changes while it runs, including the random state
+// it was run with and the attacks of every track. a snapshot can only be
+// restored into the solver it was taken from, or one with the same tracks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolverSnapshot {
+    bytes: Vec<u8>,
+}
+
+impl SolverSnapshot {
+    #[must_use]
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self { bytes }
+    }
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+#[derive(Default)]
+pub struct SnapshotWriter {
+    bytes: Vec<u8>,
+    // when set, written bytes are folded into this FNV-1a hash instead of kept.
+    hash: Option<u64>,
+}
+
+impl SnapshotWriter {
+    // a writer that only hashes what is written to it, without allocating.
+    #[must_use]
+    pub fn hashing() -> Self {
+        Self {
+            bytes: vec![],
+            hash: Some(FNV_OFFSET_BASIS),
+        }
+    }
+    #[must_use]
+    pub fn finish_hash(&self) -> Option<u64> {
+        self.hash
+    }
+    fn push(&mut self, byte: u8) {
+        match &mut self.hash {
+            Some(hash) => *hash = (*hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME),
+            None => self.bytes.push(byte),
+        }
+    }
+    pub fn write<T: SnapshotState>(&mut self, value: &T) {
+        value.write(self);
+    }
+    #[must_use]
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+    // unsigned LEB128, most values in a solver are small frame counts.
+    fn write_varint(&mut self, mut value: u64) {
+        loop {
+            let byte = (value & 0x7f) as u8;
+            value >>= 7;
+            if value == 0 {
+                self.push(byte);
+                return;
+            }
+            self.push(byte | 0x80);
+        }
+    }
+}
+
+pub struct SnapshotReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> SnapshotReader<'a> {
+    #[must_use]
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+    pub fn read<T: SnapshotState>(&mut self) -> Option<T> {
+        T::read(self)
+    }
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+    // what has not been read yet, for when the rest is in another format.
+    #[must_use]
+    pub fn remaining(&self) -> &'a [u8] {
+        self.bytes
+    }
+    fn read_varint(&mut self) -> Option<u64> {
+        let mut value: u64 = 0;
+        for shift in (0..64).step_by(7) {
+            let (byte, rest) = self.bytes.split_first()?;
+            self.bytes = rest;
+            value |= u64::from(byte & 0x7f).checked_shl(shift)?;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+}
+
+pub trait SnapshotState: Sized {
+    fn write(&self, writer: &mut SnapshotWriter);
+    fn read(reader: &mut SnapshotReader) -> Option<Self>;
+}
+
+impl SnapshotState for u64 {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write_varint(*self);
+    }
+    fn read(reader: &mut SnapshotReader) -> Option<Self> {
+        reader.read_varint()
+    }
+}
+
+impl SnapshotState for u32 {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write_varint(u64::from(*self));
+    }
+    fn read(reader: &mut SnapshotReader) -> Option<Self> {
+        reader.read_varint().and_then(|v| Self::try_from(v).ok())
+    }
+}
+
+impl SnapshotState for u8 {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write_varint(u64::from(*self));
+    }
+    fn read(reader: &mut SnapshotReader) -> Option<Self> {
+        reader.read_varint().and_then(|v| Self::try_from(v).ok())
+    }
+}
+
+impl SnapshotState for usize {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write_varint(u64::try_from(*self).expect("usize out of u64 range"));
+    }
+    fn read(reader: &mut SnapshotReader) -> Option<Self> {
+        reader.read_varint().and_then(|v| Self::try_from(v).ok())
+    }
+}
+
+impl SnapshotState for bool {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write_varint(u64::from(*self));
+    }
+    fn read(reader: &mut SnapshotReader) -> Option<Self> {
+        match reader.read_varint()? {
+            0 => Some(false),
+            1 => Some(true),
+            _ => None,
+        }
+    }
+}
+
+impl SnapshotState for NonZeroI64 {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write_varint(self.get().cast_unsigned());
+    }
+    fn read(reader: &mut SnapshotReader) -> Option<Self> {
+        reader
+            .read_varint()
+            .and_then(|v| Self::new(v.cast_signed()))
+    }
+}
+
+impl<T: SnapshotState> SnapshotState for Option<T> {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.is_some());
+        if let Some(value) = self {
+            writer.write(value);
+        }
+    }
+    fn read(reader: &mut SnapshotReader) -> Option<Self> {
+        if reader.read::<bool>()? {
+            reader.read().map(Some)
+        } else {
+            Some(None)
+        }
+    }
+}
+
+impl<T: SnapshotState> SnapshotState for Vec<T> {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.len());
+        for value in self {
+            writer.write(value);
+        }
+    }
+    fn read(reader: &mut SnapshotReader) -> Option<Self> {
+        let len: usize = reader.read()?;
+        // every value takes at least one byte, so this never allocates for garbage lengths.
+        if len > reader.bytes.len() {
+            return None;
+        }
+        (0..len).map(|_| reader.read()).collect()
+    }
+}
+
+impl<A: SnapshotState, B: SnapshotState> SnapshotState for (A, B) {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.0);
+        writer.write(&self.1);
+    }
+    fn read(reader: &mut SnapshotReader) -> Option<Self> {
+        Some((reader.read()?, reader.read()?))
+    }
+}
+
+impl Solver {
+    #[must_use]
+    pub fn snapshot(&self, random: SeededRandom) -> SolverSnapshot {
+        let mut writer = SnapshotWriter::default();
+        self.write_state(&mut writer, random);
+        SolverSnapshot {
+            bytes: writer.bytes,
+        }
+    }
+    // a hash of everything a snapshot holds, two solvers with the same hash
+    // schedule the same attacks from here on.
+    #[must_use]
+    pub fn state_hash(&self, random: SeededRandom) -> u64 {
+        let mut writer = SnapshotWriter::hashing();
+        self.write_state(&mut writer, random);
+        writer.finish_hash().unwrap_or_default()
+    }
+    fn write_state(&self, writer: &mut SnapshotWriter, random: SeededRandom) {
+        writer.write(&random.get_state());
+        writer.write(&self.time_now_frames);
+        writer.write(&self.lead_track_id);
+        writer.write(&self.lead_request);
+        writer.write(&self.difficulty);
+        writer.write(&self.adaptive_difficulty);
+        writer.write(&self.planning_horizon);
+        writer.write(&self.filler_slack);
+        writer.write(&self.removal_grace);
+        writer.write(&self.step_budget);
+        writer.write(&self.lead_handoff);
+        writer.write(&self.tracks.len());
+        for (id, track) in self.tracks.iter() {
+            writer.write(&id);
+            track.write_state(writer);
+        }
+    }
+    // returns false and leaves everything untouched if the snapshot can not be
+    // read, or was taken from a solver with different tracks.
+    pub fn restore(&mut self, snapshot: &SolverSnapshot, random: &mut SeededRandom) -> bool {
+        let mut reader = SnapshotReader::new(snapshot.as_bytes());
+        let Some(restored) = RestoredSolver::read(&mut reader) else {
+            return false;
+        };
+        if !reader.is_empty() || restored.tracks.len() != self.tracks.len() {
+            return false;
+        }
+        // tracks are written in order of their ids, this also rules out duplicates.
+        if !restored.tracks.is_sorted_by(|(a, _), (b, _)| a < b) {
+            return false;
+        }
+        let tracks_match = restored.tracks.iter().all(|(id, state)| {
+            self.tracks.get(*id).is_some() && EnemyTrack::can_restore_state(state)
+        });
+        if !tracks_match {
+            return false;
+        }
+
+        *random = SeededRandom::new(restored.random_state);
+        self.time_now_frames = restored.time_now_frames;
+        self.lead_track_id = restored.lead_track_id;
+        self.lead_request = restored.lead_request;
+        self.difficulty = restored.difficulty;
+        self.adaptive_difficulty = restored.adaptive_difficulty;
+        self.planning_horizon = restored.planning_horizon;
+        self.filler_slack = restored.filler_slack;
+        self.removal_grace = restored.removal_grace;
+        self.step_budget = restored.step_budget;
+        self.lead_handoff = restored.lead_handoff;
+        for (id, state) in restored.tracks {
+            self.get_track_mut(id).restore_state(state);
+        }
+        true
+    }
+}
+
+struct RestoredSolver {
+    random_state: u64,
+    time_now_frames: u64,
+    lead_track_id: Option<NonZeroI64>,
+    lead_request: Option<ComplementAttackRequest>,
+    difficulty: Difficulty,
+    adaptive_difficulty: Option<AdaptiveDifficulty>,
+    planning_horizon: u64,
+    filler_slack: u64,
+    removal_grace: u64,
+    step_budget: u64,
+    lead_handoff: LeadHandoff,
+    tracks: Vec<(NonZeroI64, EnemyTrackState)>,
+}
+
+impl RestoredSolver {
+    fn read(reader: &mut SnapshotReader) -> Option<Self> {
+        Some(Self {
+            random_state: reader.read()?,
+            time_now_frames: reader.read()?,
+            lead_track_id: reader.read()?,
+            lead_request: reader.read()?,
+            difficulty: reader.read()?,
+            adaptive_difficulty: reader.read()?,
+            planning_horizon: reader.read()?,
+            filler_slack: reader.read()?,
+            removal_grace: reader.read()?,
+            step_budget: reader.read()?,
+            lead_handoff: reader.read()?,
+            tracks: reader.read()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod snapshot_tests {
+    use super::*;
+
+    #[test]
+    fn varint_round_trip() {
+        let values = [0, 1, 127, 128, 300, u64::MAX];
+        let mut writer = SnapshotWriter::default();
+        for value in values {
+            writer.write(&value);
+        }
+        writer.write(&NonZeroI64::new(-5).unwrap());
+        writer.write(&Some(vec![true, false]));
+        assert_eq!(writer.bytes.len(), 1 + 1 + 1 + 2 + 2 + 10 + 10 + 4);
+
+        let mut reader = SnapshotReader::new(&writer.bytes);
+        for value in values {
+            assert_eq!(reader.read::<u64>(), Some(value));
+        }
+        assert_eq!(reader.read::<NonZeroI64>(), NonZeroI64::new(-5));
+        assert_eq!(
+            reader.read::<Option<Vec<bool>>>(),
+            Some(Some(vec![true, false]))
+        );
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn hash_follows_written_bytes() {
+        let mut hasher = SnapshotWriter::hashing();
+        assert_eq!(hasher.finish_hash(), Some(FNV_OFFSET_BASIS));
+        hasher.write(&300u64);
+        let mut other = SnapshotWriter::hashing();
+        other.write(&301u64);
+        assert_ne!(hasher.finish_hash(), other.finish_hash());
+        assert!(hasher.bytes.is_empty());
+        assert_eq!(SnapshotWriter::default().finish_hash(), None);
+    }
+
+    #[test]
+    fn rejects_garbage() {
+        let mut reader = SnapshotReader::new(&[0xff; 11]);
+        assert_eq!(reader.read::<u64>(), None);
+        let mut reader = SnapshotReader::new(&[0x7f]);
+        assert_eq!(reader.read::<Vec<u64>>(), None);
+        let mut solver = Solver::new();
+        let mut random = SeededRandom::new(1);
+        assert!(!solver.restore(&SolverSnapshot::from_bytes(vec![1, 2, 3]), &mut random));
+        assert_eq!(random, SeededRandom::new(1));
+    }
+}
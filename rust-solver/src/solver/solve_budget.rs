@@ -0,0 +1,88 @@
+use std::time::Instant;
+
+// how much work a single call to solve may do. a step is one request frame the
+// tracks are searched for, or one attack queued for the lead, which is what
+// the time of a solve grows with. whatever is left over is picked up by the
+// next solve, as long as the request frames are still ahead.
+//
+// a deadline makes the schedule depend on how fast the machine is, so it can
+// not be replayed. sessions only use a step budget for that reason.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SolveBudget {
+    steps_left: Option<u64>,
+    deadline: Option<Instant>,
+    // set once a step was asked for that the budget did not have.
+    ran_out: bool,
+}
+
+impl SolveBudget {
+    #[must_use]
+    pub fn unlimited() -> Self {
+        Self {
+            steps_left: None,
+            deadline: None,
+            ran_out: false,
+        }
+    }
+    #[must_use]
+    pub fn steps(steps: u64) -> Self {
+        Self {
+            steps_left: Some(steps),
+            deadline: None,
+            ran_out: false,
+        }
+    }
+    // also stops once `deadline` has passed, whichever comes first.
+    #[must_use]
+    pub fn until(self, deadline: Instant) -> Self {
+        Self {
+            deadline: Some(deadline),
+            ..self
+        }
+    }
+    // takes one step, returns false if there was none left.
+    pub fn spend(&mut self) -> bool {
+        if self.is_spent() {
+            self.ran_out = true;
+            return false;
+        }
+        if let Some(steps) = &mut self.steps_left {
+            *steps -= 1;
+        }
+        true
+    }
+    // true if some work was left undone, a budget that is used up exactly by
+    // the work there was has not run out.
+    #[must_use]
+    pub fn ran_out(&self) -> bool {
+        self.ran_out
+    }
+    #[must_use]
+    pub fn is_spent(&self) -> bool {
+        self.steps_left == Some(0) || self.deadline.is_some_and(|end| Instant::now() >= end)
+    }
+}
+
+#[cfg(test)]
+mod solve_budget_tests {
+    use super::*;
+
+    #[test]
+    fn spends_steps() {
+        let mut budget = SolveBudget::steps(2);
+        assert!(budget.spend() && budget.spend());
+        assert!(budget.is_spent() && !budget.ran_out());
+        assert!(!budget.spend());
+        assert!(budget.ran_out());
+
+        let mut unlimited = SolveBudget::unlimited();
+        assert!((0..1000).all(|_| unlimited.spend()));
+    }
+
+    #[test]
+    fn stops_at_deadline() {
+        let mut budget = SolveBudget::unlimited().until(Instant::now());
+        assert!(budget.is_spent());
+        assert!(!budget.spend());
+    }
+}
@@ -0,0 +1,34 @@
+use crate::enemy_track::complement_attack_request::ComplementAttackRequest;
+use crate::enemy_track::complement_attack_request::request_index::RequestIndex;
+use crate::enemy_track::future_move_commit::FutureMoveCommit;
+use crate::solver::zone_occupancy::ZoneOccupancy;
+use std::num::NonZeroI64;
+
+// memory the solver keeps between frames so that solving stops allocating once
+// it has grown to fit the encounter. none of it is part of the state of the
+// solver, it is only filled and read within a single call.
+#[derive(Debug, Default)]
+pub struct SolveScratch {
+    // the options of every track for the current request frame, by the
+    // position of the track.
+    pub track_options: Vec<Vec<FutureMoveCommit>>,
+    // every track that has options, with its position.
+    pub candidates: Vec<(NonZeroI64, usize)>,
+    // options of a single track, for the lead and for fillers.
+    pub options: Vec<FutureMoveCommit>,
+    pub planned_hits: Vec<u64>,
+    pub zones: ZoneOccupancy,
+    pub open: RequestIndex,
+    // requests that were cleared, new ones are built in their memory. handing
+    // off the lead builds one while the old one is still in use, so there can
+    // be two.
+    pub spare_requests: Vec<ComplementAttackRequest>,
+}
+
+// a copy of a solver starts without the memory, it only grows it again if it
+// is used to solve.
+impl Clone for SolveScratch {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
@@ -0,0 +1,106 @@
+use crate::enemy_track::EnemyTrack;
+#[cfg(all(feature = "parallel", not(feature = "nothreads")))]
+use rayon::prelude::*;
+use std::collections::HashMap;
+use std::num::NonZeroI64;
+
+// tracks kept in order of their ids, with a lookup from id to position.
+//
+// iterating a `HashMap` gives a different order on every run, which changes the
+// schedule even with the same random state. id order is used over insertion
+// order because snapshots and replays add their tracks in id order too.
+// adding and removing tracks is O(n), looking them up stays O(1).
+#[derive(Debug, Default, Clone)]
+pub struct TrackMap {
+    entries: Vec<(NonZeroI64, EnemyTrack)>,
+    positions: HashMap<NonZeroI64, usize>,
+}
+
+impl TrackMap {
+    // replaces the track if the id is already used.
+    pub fn insert(&mut self, id: NonZeroI64, track: EnemyTrack) {
+        if let Some(position) = self.positions.get(&id) {
+            self.entries[*position].1 = track;
+            return;
+        }
+        let position = self.entries.partition_point(|(other, _)| *other < id);
+        self.entries.insert(position, (id, track));
+        self.update_positions(position);
+    }
+    pub fn remove(&mut self, id: NonZeroI64) -> Option<EnemyTrack> {
+        let position = self.positions.remove(&id)?;
+        let (_, track) = self.entries.remove(position);
+        self.update_positions(position);
+        Some(track)
+    }
+    fn update_positions(&mut self, from: usize) {
+        for (position, (id, _)) in self.entries.iter().enumerate().skip(from) {
+            self.positions.insert(*id, position);
+        }
+    }
+    #[must_use]
+    pub fn get(&self, id: NonZeroI64) -> Option<&EnemyTrack> {
+        self.positions
+            .get(&id)
+            .map(|position| &self.entries[*position].1)
+    }
+    pub fn get_mut(&mut self, id: NonZeroI64) -> Option<&mut EnemyTrack> {
+        self.positions
+            .get(&id)
+            .map(|position| &mut self.entries[*position].1)
+    }
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    pub fn ids(&self) -> impl Iterator<Item = NonZeroI64> {
+        self.entries.iter().map(|(id, _)| *id)
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (NonZeroI64, &EnemyTrack)> {
+        self.entries.iter().map(|(id, track)| (*id, track))
+    }
+    #[cfg(all(feature = "parallel", not(feature = "nothreads")))]
+    pub fn par_iter(&self) -> impl IndexedParallelIterator<Item = (NonZeroI64, &EnemyTrack)> {
+        self.entries.par_iter().map(|(id, track)| (*id, track))
+    }
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (NonZeroI64, &mut EnemyTrack)> {
+        self.entries.iter_mut().map(|(id, track)| (*id, track))
+    }
+    pub fn values(&self) -> impl Iterator<Item = &EnemyTrack> {
+        self.entries.iter().map(|(_, track)| track)
+    }
+}
+
+#[cfg(test)]
+mod track_map_tests {
+    use super::*;
+
+    fn id(value: i64) -> NonZeroI64 {
+        NonZeroI64::new(value).unwrap()
+    }
+
+    #[test]
+    fn iterates_in_id_order() {
+        let mut tracks = TrackMap::default();
+        for value in [5, -3, 9, 1] {
+            tracks.insert(id(value), EnemyTrack::new(vec![]));
+        }
+        assert_eq!(
+            tracks.ids().collect::<Vec<_>>(),
+            [id(-3), id(1), id(5), id(9)]
+        );
+
+        assert!(tracks.remove(id(1)).is_some());
+        assert!(tracks.remove(id(1)).is_none());
+        tracks.insert(id(7), EnemyTrack::new(vec![]));
+        assert_eq!(
+            tracks.ids().collect::<Vec<_>>(),
+            [id(-3), id(5), id(7), id(9)]
+        );
+        assert_eq!(tracks.len(), 4);
+        for value in [-3, 5, 7, 9] {
+            assert!(tracks.get(id(value)).is_some());
+        }
+        assert!(tracks.get(id(1)).is_none());
+    }
+}
@@ -0,0 +1,110 @@
+use crate::enemy_track::EnemyTrack;
+use crate::enemy_track::complement_attack_request::commit_key::CommitKey;
+use crate::enemy_track::future_move_commit::FutureMoveCommit;
+use crate::solver::track_map::TrackMap;
+use std::num::NonZeroI64;
+use std::ops::Range;
+
+// the zones the planned commits of every track take up, so that a commit that
+// would share a zone with another track at the same time is never planned,
+// unless it answers the commit it shares the zone with. empty as long as no
+// attack uses zones, which keeps the check free then.
+#[derive(Debug, Default)]
+pub struct ZoneOccupancy {
+    taken: Vec<(CommitKey, u32, Range<u64>)>,
+}
+
+impl ZoneOccupancy {
+    pub fn rebuild(&mut self, tracks: &TrackMap) {
+        self.taken.clear();
+        for (track_id, track) in tracks.iter() {
+            for commit in track.future_commits() {
+                self.add(track_id, track, commit);
+            }
+        }
+    }
+    pub fn add(&mut self, track_id: NonZeroI64, track: &EnemyTrack, commit: &FutureMoveCommit) {
+        let key = CommitKey::new(track_id, commit.get_start_frame());
+        self.taken.extend(
+            track
+                .get_attack(commit.get_index())
+                .get_zones()
+                .iter()
+                .map(|zone| {
+                    let frames = zone.frames_from(commit.get_start_frame());
+                    (key, zone.get_zones(), frames)
+                }),
+        );
+    }
+    // false if the commit takes up a zone another track has at the same time.
+    // `requester` is the commit it answers, which it may share zones with.
+    #[must_use]
+    pub fn allows(
+        &self,
+        track_id: NonZeroI64,
+        track: &EnemyTrack,
+        commit: &FutureMoveCommit,
+        requester: Option<CommitKey>,
+    ) -> bool {
+        if self.taken.is_empty() {
+            return true;
+        }
+        track
+            .get_attack(commit.get_index())
+            .get_zones()
+            .iter()
+            .all(|zone| {
+                let frames = zone.frames_from(commit.get_start_frame());
+                !self.taken.iter().any(|(other, zones, taken)| {
+                    other.get_track_id() != track_id
+                        && Some(*other) != requester
+                        && zones & zone.get_zones() != 0
+                        && taken.start < frames.end
+                        && frames.start < taken.end
+                })
+            })
+    }
+}
+
+#[cfg(test)]
+mod zone_occupancy_tests {
+    use super::*;
+    use crate::attack::Attack;
+    use crate::attack::zone_use::ZoneUse;
+
+    fn track_in(zones: u32) -> EnemyTrack {
+        let attack = Attack::new_expect(10, vec![5], vec![])
+            .with_zones(vec![ZoneUse::new(zones, 2, 8).unwrap()])
+            .unwrap();
+        EnemyTrack::new(vec![attack])
+    }
+
+    #[test]
+    fn keeps_tracks_out_of_taken_zones() {
+        let (a, b) = (NonZeroI64::new(1).unwrap(), NonZeroI64::new(2).unwrap());
+        let mut tracks = TrackMap::default();
+        let mut left = track_in(0b01);
+        left.commit_by_index(0, 10, 0);
+        tracks.insert(a, left);
+        let mut occupancy = ZoneOccupancy::default();
+        occupancy.rebuild(&tracks);
+
+        let commit = |start| FutureMoveCommit::try_create(0, start, 0).unwrap();
+        // the left lane is taken from frame 12 until 18.
+        assert!(!occupancy.allows(b, &track_in(0b01), &commit(5), None));
+        assert!(!occupancy.allows(b, &track_in(0b11), &commit(15), None));
+        assert!(occupancy.allows(b, &track_in(0b01), &commit(4), None));
+        assert!(occupancy.allows(b, &track_in(0b01), &commit(16), None));
+        assert!(occupancy.allows(b, &track_in(0b10), &commit(10), None));
+        assert!(occupancy.allows(a, &track_in(0b01), &commit(10), None));
+        // the commit that is answered shares its zones, no other one does.
+        assert!(occupancy.allows(b, &track_in(0b01), &commit(5), Some(CommitKey::new(a, 10))));
+        assert!(!occupancy.allows(b, &track_in(0b01), &commit(5), Some(CommitKey::new(a, 0))));
+        assert!(occupancy.allows(
+            b,
+            &EnemyTrack::new(vec![Attack::new_expect(10, vec![5], vec![])]),
+            &commit(10),
+            None
+        ));
+    }
+}
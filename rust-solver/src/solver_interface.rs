@@ -1,28 +1,44 @@
+use crate::attack::Attack;
 use crate::enemy_track::EnemyTrack;
+use crate::enemy_track::complement_attack_request::commit_key::CommitKey;
 use crate::enemy_track::future_move_commit::FutureMoveCommit;
-use crate::solver::Solver;
+use crate::enemy_track::track_phases::TrackPhases;
+use crate::solver::adaptive_difficulty::AdaptiveDifficulty;
+use crate::solver::adaptive_difficulty::MAX_INTENSITY;
+use crate::solver::adaptive_difficulty::PerformanceEvent;
+use crate::solver::difficulty::Difficulty;
+use crate::solver::difficulty::DifficultyPreset;
+use crate::solver::lead_handoff::LeadHandoff;
 use crate::solver_interface::extern_enemy_track::ExternEnemyTrack;
-use crate::solver_interface::godot_random::GodotRandom;
+use crate::solver_session::SolverSession;
+use crate::solver_session::session_input::SessionInput;
 use godot::classes::INode;
 use godot::classes::Node;
 use godot::global::godot_print;
+use godot::global::randi;
 use godot::obj::Gd;
 use godot::obj::WithBaseField;
+use godot::prelude::Array;
 use godot::prelude::Base;
+use godot::prelude::GString;
 use godot::prelude::GodotClass;
+use godot::prelude::PackedByteArray;
+use godot::prelude::PackedStringArray;
+use godot::prelude::VarDictionary;
 use godot::prelude::godot_api;
+use godot::prelude::vdict;
 use std::num::NonZeroI64;
+use std::time::Duration;
 
 mod extern_enemy_attack;
 mod extern_enemy_track;
-mod godot_random;
+mod extern_track_phase;
 
 #[derive(GodotClass)]
 #[class(base=Node)]
 struct SolverInterface {
     base: Base<Node>,
-    reset_needed: bool,
-    solver: Solver,
+    session: SolverSession,
 }
 
 #[godot_api]
@@ -30,20 +46,11 @@ impl INode for SolverInterface {
     fn init(base: Base<Node>) -> Self {
         Self {
             base,
-            reset_needed: false,
-            solver: Solver::new(),
+            session: SolverSession::new(randi().cast_unsigned()),
         }
     }
     fn physics_process(&mut self, _delta: f64) {
-        let mut random = GodotRandom {};
-
-        self.solver.update_latest_nonpast();
-        if self.reset_needed {
-            self.reset_needed = false;
-            self.solver.reset_non_current();
-        }
-        self.solver.solve(&mut random);
-        self.solver.tick();
+        self.session.step();
     }
 }
 
@@ -52,72 +59,317 @@ impl SolverInterface {
     #[func]
     fn add_track(&mut self, mut extern_track: Gd<ExternEnemyTrack>) {
         extern_track.bind_mut().parent_to_solver(self.to_gd());
-        let attacks = extern_track.bind().convert_to_attacks_internal();
-        let track = EnemyTrack::new(attacks);
         let index = extern_track.bind().get_id();
-        self.solver.add_track(index, track);
+        self.session.apply(SessionInput::AddTrack {
+            id: index,
+            attacks: extern_track.bind().convert_to_attacks_internal(),
+            resource_pool: extern_track.bind().get_resource_pool(),
+            phases: extern_track.bind().get_track_phases(),
+        });
         godot_print!("added track: {}", index);
     }
     #[func]
     fn remove_track(&mut self, mut extern_track: Gd<ExternEnemyTrack>) {
         extern_track.bind_mut().unparent_from_solver();
         let index = extern_track.bind().get_id();
-        self.solver.remove_track(index);
+        self.session.apply(SessionInput::RemoveTrack(index));
         godot_print!("removed track: {}", index);
     }
+    #[func]
+    fn set_seed(&mut self, seed: i64) {
+        self.session
+            .apply(SessionInput::SetSeed(seed.cast_unsigned()));
+    }
+    // everything needed to go back to this frame with `restore_snapshot`, as
+    // long as the same tracks are added when it is restored.
+    #[func]
+    fn save_snapshot(&self) -> PackedByteArray {
+        PackedByteArray::from(self.session.save_snapshot())
+    }
+    #[func]
+    #[allow(clippy::needless_pass_by_value)]
+    fn restore_snapshot(&mut self, bytes: PackedByteArray) -> bool {
+        self.session
+            .apply(SessionInput::RestoreSnapshot(bytes.to_vec()))
+    }
+    // changes whenever anything that affects scheduling changes, peers running
+    // the same inputs should always have the same hash on the same frame.
+    #[func]
+    fn get_state_hash(&self) -> i64 {
+        self.session.state_hash().cast_signed()
+    }
+    // records every input from now on, until `stop_recording` is called.
+    #[func]
+    fn start_recording(&mut self) {
+        self.session.start_recording();
+    }
+    // the recording as bytes to save to a file and replay with the `replay`
+    // binary, empty if nothing was being recorded.
+    #[func]
+    fn stop_recording(&mut self) -> PackedByteArray {
+        self.session
+            .stop_recording()
+            .map(|recording| PackedByteArray::from(recording.to_bytes()))
+            .unwrap_or_default()
+    }
+    // how many frames ahead of now the lead's attacks should be planned.
+    #[func]
+    fn set_planning_horizon(&mut self, frames: u32) {
+        self.session
+            .apply(SessionInput::SetPlanningHorizon(u64::from(frames)));
+    }
+    // longest filler that may be given to a track with nothing to do, 0 turns fillers off.
+    #[func]
+    fn set_filler_slack(&mut self, frames: u32) {
+        self.session
+            .apply(SessionInput::SetFillerSlack(u64::from(frames)));
+    }
+    // frames after a track is removed before other tracks may take over what it
+    // was going to do.
+    #[func]
+    fn set_removal_grace(&mut self, frames: u32) {
+        self.session
+            .apply(SessionInput::SetRemovalGrace(u64::from(frames)));
+    }
+    // most request frames searched per physics frame, the rest is searched on
+    // the frames after. 0 searches all of them at once.
+    #[func]
+    fn set_step_budget(&mut self, steps: u32) {
+        self.session
+            .apply(SessionInput::SetStepBudget(u64::from(steps)));
+    }
+    // the longest a physics frame may spend solving, 0 does not limit it.
+    // recordings and peers kept in sync will not match with this set, as what
+    // gets planned depends on the speed of the machine.
+    #[func]
+    fn set_time_budget_usec(&mut self, usec: u32) {
+        self.session
+            .set_time_budget((usec > 0).then(|| Duration::from_micros(u64::from(usec))));
+    }
+    // solves on a worker thread instead, a solve is visible `frames` frames
+    // after it starts and only plans from then on. 0 solves every frame on the
+    // main thread.
+    //
+    // the frame a solve is visible on waits for the worker if it is not done
+    // yet. any change to tracks or settings while it runs makes it stale, and
+    // that frame solves on the main thread instead, so changes every few
+    // frames cost as much as no latency. performance reports do not, they
+    // only reach the difficulty once the solve is visible.
+    #[func]
+    fn set_solve_latency(&mut self, frames: u32) {
+        self.session
+            .apply(SessionInput::SetSolveLatency(u64::from(frames)));
+    }
+    // how often a solve on the worker was stale and the main thread solved
+    // instead, to check if the latency is worth it.
+    #[func]
+    fn get_fallback_solves(&self) -> i64 {
+        i64::try_from(self.session.get_fallback_solves()).unwrap_or(i64::MAX)
+    }
+    // who the lead goes to once its attack is over, one of "random" or
+    // "latest_ending". returns false if the name is unknown.
+    #[func]
+    fn set_lead_handoff(&mut self, name: GString) -> bool {
+        let Some(handoff) = LeadHandoff::from_name(&String::from(name)) else {
+            return false;
+        };
+        self.session.apply(SessionInput::SetLeadHandoff(handoff))
+    }
+    // takes one of "easy", "normal" or "hard", returns false if the name is unknown.
+    #[func]
+    fn set_difficulty_preset(&mut self, name: GString) -> bool {
+        let Some(preset) = DifficultyPreset::from_name(&String::from(name)) else {
+            return false;
+        };
+        self.session
+            .apply(SessionInput::SetDifficulty(Difficulty::from(preset)))
+    }
+    #[func]
+    fn set_fill_percent(&mut self, percent: u32) {
+        self.change_difficulty(|difficulty| difficulty.set_fill_percent(percent));
+    }
+    #[func]
+    fn set_min_hit_spacing(&mut self, frames: u32) {
+        self.change_difficulty(|difficulty| difficulty.set_min_hit_spacing(u64::from(frames)));
+    }
+    // negative values remove the limit.
+    #[func]
+    fn set_max_complementing_tracks(&mut self, max: i64) {
+        self.change_difficulty(|difficulty| {
+            difficulty.set_max_complementing_tracks(usize::try_from(max).ok());
+        });
+    }
+    // negative values make every attack preferred.
+    #[func]
+    fn set_max_preferred_tier(&mut self, tier: i64) {
+        self.change_difficulty(|difficulty| {
+            difficulty.set_max_preferred_tier(u8::try_from(tier).ok());
+        });
+    }
+    // lets player performance pick the difficulty between two presets, setting
+    // any difficulty directly turns this off again.
+    #[func]
+    fn enable_adaptive_difficulty(&mut self, easiest: GString, hardest: GString) -> bool {
+        let (Some(easiest), Some(hardest)) = (
+            DifficultyPreset::from_name(&String::from(easiest)),
+            DifficultyPreset::from_name(&String::from(hardest)),
+        ) else {
+            return false;
+        };
+        self.session.apply(SessionInput::SetAdaptiveDifficulty(Some(
+            AdaptiveDifficulty::new(easiest.into(), hardest.into()),
+        )))
+    }
+    #[func]
+    fn disable_adaptive_difficulty(&mut self) {
+        self.session
+            .apply(SessionInput::SetAdaptiveDifficulty(None));
+    }
+    // how much intensity each event adds or removes, out of 1000.
+    #[func]
+    fn set_adaptive_steps(&mut self, success: u32, missed: u32, damage: u32) {
+        self.change_adaptive_difficulty(|adaptive| adaptive.set_steps(success, missed, damage));
+    }
+    // how much intensity can change every physics frame, out of 1000.
+    #[func]
+    fn set_adaptive_change_per_frame(&mut self, change: u32) {
+        self.change_adaptive_difficulty(|adaptive| adaptive.set_change_per_frame(change));
+    }
+    #[func]
+    fn report_parry_success(&mut self) {
+        self.session.apply(SessionInput::ReportPerformance(
+            PerformanceEvent::ParrySuccess,
+        ));
+    }
+    #[func]
+    fn report_parry_missed(&mut self) {
+        self.session.apply(SessionInput::ReportPerformance(
+            PerformanceEvent::ParryMissed,
+        ));
+    }
+    #[func]
+    fn report_damage_taken(&mut self) {
+        self.session.apply(SessionInput::ReportPerformance(
+            PerformanceEvent::DamageTaken,
+        ));
+    }
+    // intensity from 0 to 1, or -1 if adaptive difficulty is off.
+    #[func]
+    fn get_intensity(&self) -> f64 {
+        self.session
+            .get_solver()
+            .get_adaptive_difficulty()
+            .map_or(-1.0, |adaptive| {
+                f64::from(adaptive.get_intensity()) / f64::from(MAX_INTENSITY)
+            })
+    }
+    // a dictionary for every frame of the current request: "frame", the
+    // "origin_track" and "origin_attack" that asked for it, the
+    // "claimant_track", "claimant_attack" and "claimant_start" of the commit
+    // that took it, and whether it was "declined". tracks are 0, attacks and
+    // frames -1 where there are none.
+    #[func]
+    fn get_request_frames(&self) -> Array<VarDictionary> {
+        let solver = self.session.get_solver();
+        let frame = |frame: u64| i64::try_from(frame).unwrap_or(i64::MAX);
+        let track = |key: Option<CommitKey>| key.map_or(0, |key| key.get_track_id().get());
+        let attack = |key: Option<CommitKey>| {
+            key.and_then(|key| solver.commit_attack(key))
+                .and_then(|index| i64::try_from(index).ok())
+                .unwrap_or(-1)
+        };
+        solver
+            .request_frames()
+            .map(|request_frame| {
+                let (origin, claimant) = (request_frame.get_origin(), request_frame.get_claimant());
+                vdict! {
+                    "frame": frame(request_frame.get_frame()),
+                    "origin_track": track(origin),
+                    "origin_attack": attack(origin),
+                    "claimant_track": track(claimant),
+                    "claimant_attack": attack(claimant),
+                    "claimant_start": claimant.map_or(-1, |key| frame(key.get_start_frame())),
+                    "declined": request_frame.is_declined(),
+                }
+            })
+            .collect()
+    }
+    // everything wrong with the current plan, for a debug overlay. empty
+    // while the plan is sound.
+    #[func]
+    fn get_plan_violations(&self) -> PackedStringArray {
+        self.session
+            .get_solver()
+            .validate_plan()
+            .iter()
+            .map(|violation| GString::from(&violation.to_string()))
+            .collect()
+    }
 }
 
 impl SolverInterface {
-    fn queue_reset(&mut self) {
-        self.reset_needed = true;
+    // difficulty changes go through the session as a whole new difficulty so
+    // that they are recorded.
+    fn change_difficulty(&mut self, change: impl FnOnce(&mut Difficulty)) {
+        let mut difficulty = *self.session.get_solver().get_difficulty();
+        change(&mut difficulty);
+        self.session.apply(SessionInput::SetDifficulty(difficulty));
+    }
+    fn change_adaptive_difficulty(&mut self, change: impl FnOnce(&mut AdaptiveDifficulty)) {
+        let Some(mut adaptive) = self.session.get_solver().get_adaptive_difficulty().copied()
+        else {
+            return;
+        };
+        change(&mut adaptive);
+        self.session
+            .apply(SessionInput::SetAdaptiveDifficulty(Some(adaptive)));
     }
     pub fn time_now(&self) -> u64 {
-        self.solver.time_now_frames()
+        self.session.time_now()
     }
     pub fn commit_move_now(&mut self, id: NonZeroI64, index: usize) {
-        let time_now = self.time_now();
-        if !self.solver.all_tracks_actionable(time_now) {
-            return;
-        }
         if self
-            .solver
-            .get_track_mut(id)
-            .commit_by_index(index, time_now, time_now)
+            .session
+            .apply(SessionInput::CommitMoveNow { id, index })
         {
-            self.solver.change_lead(id);
             godot_print!("sucessfully committed move");
         }
     }
     pub fn change_move_validity(&mut self, id: NonZeroI64, index: usize, valid: bool) {
-        self.solver.get_track_mut(id).set_validity(index, valid);
-        if !valid {
-            self.queue_reset();
-        }
+        self.session
+            .apply(SessionInput::SetAttackValidity { id, index, valid });
     }
     pub fn reset_track_validity(&mut self, id: NonZeroI64, valid: bool) {
-        self.solver.get_track_mut(id).reset_validity(valid);
-        if !valid {
-            self.queue_reset();
-        }
+        self.session
+            .apply(SessionInput::ResetAttacksValidity { id, valid });
+    }
+    pub fn enter_phase(&mut self, id: NonZeroI64, phase: usize) -> bool {
+        self.session.apply(SessionInput::EnterPhase { id, phase })
+    }
+    pub fn get_current_phase(&self, id: NonZeroI64) -> Option<usize> {
+        self.session
+            .get_solver()
+            .find_track(id)
+            .and_then(EnemyTrack::get_phases)
+            .map(TrackPhases::get_current)
+    }
+    pub fn push_attack(&mut self, id: NonZeroI64, attack: Attack) -> bool {
+        self.session.apply(SessionInput::PushAttack { id, attack })
+    }
+    pub fn replace_attack(&mut self, id: NonZeroI64, index: usize, attack: Attack) -> bool {
+        self.session
+            .apply(SessionInput::ReplaceAttack { id, index, attack })
     }
-    pub fn get_latest_nonpast_commit(&self, id: NonZeroI64) -> Option<&FutureMoveCommit> {
-        self.solver.get_track(id).latest_nonpast_commit()
+    pub fn remove_attack(&mut self, id: NonZeroI64, index: usize) -> bool {
+        self.session.apply(SessionInput::RemoveAttack { id, index })
     }
     pub fn get_commit_on_this_frame(&self, id: NonZeroI64) -> Option<&FutureMoveCommit> {
-        // subtract one because the end of the processing cycle for the solver increments and so checking
-        // when you are outside of that cycle, you will need to effectively undo that.
-        let time_now = self.time_now().checked_sub(1);
-        self.get_latest_nonpast_commit(id)
-            .filter(|v| time_now.is_some_and(|t| v.get_start_frame().eq(&t)))
+        self.session.get_commit_on_this_frame(id)
     }
     pub fn get_active_commit(&self, id: NonZeroI64) -> Option<&FutureMoveCommit> {
-        // subtract one because the end of the processing cycle for the solver increments and so checking
-        // when you are outside of that cycle, you will need to effectively undo that.
-        let time_now = self.time_now().checked_sub(1);
-        self.get_latest_nonpast_commit(id)
-            .filter(|v| time_now.is_some_and(|t| v.get_start_frame().le(&t)))
+        self.session.get_active_commit(id)
     }
     pub fn get_current_lead(&self) -> Option<NonZeroI64> {
-        self.solver.get_lead()
+        self.session.get_solver().get_lead()
     }
 }
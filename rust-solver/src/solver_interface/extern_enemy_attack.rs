@@ -1,4 +1,5 @@
 use crate::attack::Attack;
+use crate::attack::zone_use::ZoneUse;
 use godot::classes::Resource;
 use godot::classes::class_macros::private::virtuals::Os::Array;
 use godot::obj::Gd;
@@ -15,6 +16,21 @@ pub(super) struct ExternEnemyAttack {
     requests: Array<u32>,
     #[export]
     duration: u32,
+    #[export]
+    cost: u32,
+    #[export]
+    tier: u8,
+    #[export]
+    filler: bool,
+    // lanes the attack takes up as a mask, like `parry_collision_layer`. no two
+    // tracks are planned into the same lane at once, 0 shares every lane.
+    #[export]
+    zones: u32,
+    // the frames the lanes are taken up, an end of 0 is the end of the attack.
+    #[export]
+    zone_start: u32,
+    #[export]
+    zone_end: u32,
 }
 
 impl ExternEnemyAttack {
@@ -24,6 +40,15 @@ impl ExternEnemyAttack {
     pub fn get_requests_as_vec(&self) -> Vec<u64> {
         self.requests.iter_shared().map(u64::from).collect()
     }
+    pub fn get_zone_uses(&self) -> Vec<ZoneUse> {
+        let end = match self.zone_end {
+            0 => self.duration,
+            end => end,
+        };
+        ZoneUse::new(self.zones, u64::from(self.zone_start), u64::from(end))
+            .into_iter()
+            .collect()
+    }
 }
 
 impl From<Gd<ExternEnemyAttack>> for Attack {
@@ -34,5 +59,10 @@ impl From<Gd<ExternEnemyAttack>> for Attack {
             attack.bind().get_requests_as_vec(),
         )
         .expect("invalid data!")
+        .with_cost(u64::from(attack.bind().get_cost()))
+        .with_tier(attack.bind().get_tier())
+        .with_filler(attack.bind().get_filler())
+        .with_zones(attack.bind().get_zone_uses())
+        .expect("zones taken up past the end of the attack!")
     }
 }
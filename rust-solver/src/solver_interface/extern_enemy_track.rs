@@ -1,6 +1,10 @@
 use super::extern_enemy_attack::ExternEnemyAttack;
+use super::extern_track_phase::ExternTrackPhase;
 use crate::attack::Attack;
 use crate::enemy_track::future_move_commit::FutureMoveCommit;
+use crate::enemy_track::resource_pool::ResourcePool;
+use crate::enemy_track::track_phases::Phase;
+use crate::enemy_track::track_phases::TrackPhases;
 use crate::solver_interface::SolverInterface;
 use godot::classes::Resource;
 use godot::classes::class_macros::private::virtuals::Os::Array;
@@ -18,6 +22,15 @@ pub(super) struct ExternEnemyTrack {
     base: Base<Resource>,
     #[export]
     attacks: Array<Gd<ExternEnemyAttack>>,
+    #[export]
+    has_resource_pool: bool,
+    #[export]
+    resource_capacity: u32,
+    #[export]
+    resource_regen_per_frame: u32,
+    // the track starts in the first phase, no phases enables every attack.
+    #[export]
+    phases: Array<Gd<ExternTrackPhase>>,
     solver_parent: Option<Gd<SolverInterface>>,
 }
 
@@ -52,6 +65,21 @@ impl ExternEnemyTrack {
     pub fn convert_to_attacks_internal(&self) -> Vec<Attack> {
         self.get_attacks().iter_shared().map(Attack::from).collect()
     }
+    pub fn get_track_phases(&self) -> Option<TrackPhases> {
+        if self.phases.is_empty() {
+            return None;
+        }
+        let phases = self.phases.iter_shared().map(Phase::from).collect();
+        Some(TrackPhases::new(phases).expect("phase transition to a phase that does not exist!"))
+    }
+    pub fn get_resource_pool(&self) -> Option<ResourcePool> {
+        self.has_resource_pool.then(|| {
+            ResourcePool::new(
+                u64::from(self.resource_capacity),
+                u64::from(self.resource_regen_per_frame),
+            )
+        })
+    }
 }
 
 #[godot_api]
@@ -115,6 +143,72 @@ impl ExternEnemyTrack {
             .bind_mut()
             .reset_track_validity(self.get_id(), valid);
     }
+    // moves to another phase right away, dropping what is planned with attacks
+    // the phase disables. returns false if there is no such phase.
+    #[func]
+    fn enter_phase(&self, phase: i64) -> bool {
+        let Ok(phase) = usize::try_from(phase) else {
+            return false;
+        };
+        self.get_solver_parent()
+            .bind_mut()
+            .enter_phase(self.get_id(), phase)
+    }
+    // index of the current phase, -1 if the track has no phases.
+    #[func]
+    fn current_phase(&self) -> i64 {
+        self.get_solver_parent()
+            .bind()
+            .get_current_phase(self.get_id())
+            .map_or(-1, |v| i64::try_from(v).expect("usize out of i64 range"))
+    }
+    // adds an attack to the end of the list, also while the track is in a solver.
+    #[func]
+    #[allow(clippy::needless_pass_by_value)]
+    fn push_attack(&mut self, attack: Gd<ExternEnemyAttack>) {
+        if let Some(mut solver) = self.solver_parent.clone() {
+            let id = self.get_id();
+            solver
+                .bind_mut()
+                .push_attack(id, Attack::from(attack.clone()));
+        }
+        self.attacks.push(&attack);
+    }
+    // fails if the attack is being performed right now.
+    #[func]
+    #[allow(clippy::needless_pass_by_value)]
+    fn replace_attack(&mut self, index: i64, attack: Gd<ExternEnemyAttack>) -> bool {
+        let index = usize::try_from(index).expect("index move out of range");
+        if let Some(mut solver) = self.solver_parent.clone() {
+            let id = self.get_id();
+            if !solver
+                .bind_mut()
+                .replace_attack(id, index, Attack::from(attack.clone()))
+            {
+                return false;
+            }
+        } else if index >= self.attacks.len() {
+            return false;
+        }
+        self.attacks.set(index, &attack);
+        true
+    }
+    // attacks after the removed one move down by one index, fails if the
+    // attack is being performed right now.
+    #[func]
+    fn remove_attack(&mut self, index: i64) -> bool {
+        let index = usize::try_from(index).expect("index move out of range");
+        if let Some(mut solver) = self.solver_parent.clone() {
+            let id = self.get_id();
+            if !solver.bind_mut().remove_attack(id, index) {
+                return false;
+            }
+        } else if index >= self.attacks.len() {
+            return false;
+        }
+        self.attacks.remove(index);
+        true
+    }
     // private function that runs when attacks are disabled to reset any future
     //      attacks that won't be able to happen due to disabling.
     // private function in other class to remove all attacks that depend on an attack
@@ -0,0 +1,55 @@
+use crate::enemy_track::track_phases::Phase;
+use crate::enemy_track::track_phases::PhaseTransition;
+use crate::enemy_track::track_phases::PhaseTrigger;
+use godot::classes::Resource;
+use godot::classes::class_macros::private::virtuals::Os::Array;
+use godot::obj::Gd;
+use godot::prelude::Base;
+use godot::prelude::GodotClass;
+
+#[derive(GodotClass)]
+#[class(base=Resource, init)]
+pub(super) struct ExternTrackPhase {
+    base: Base<Resource>,
+    // weight of each attack by index, attacks without a weight or with 0 are disabled.
+    #[export]
+    weights: Array<u32>,
+    #[export]
+    #[init(val = true)]
+    lead_eligible: bool,
+    // phase to move to once one of the conditions below is met, -1 for none.
+    #[export]
+    #[init(val = -1)]
+    next_phase: i64,
+    // 0 turns the condition off.
+    #[export]
+    after_frames: u32,
+    #[export]
+    after_attacks: u32,
+}
+
+impl From<Gd<ExternTrackPhase>> for Phase {
+    fn from(phase: Gd<ExternTrackPhase>) -> Self {
+        let phase = phase.bind();
+        let mut converted = Self::new(
+            phase.get_weights().iter_shared().collect(),
+            phase.get_lead_eligible(),
+        );
+        let Ok(next_phase) = usize::try_from(phase.get_next_phase()) else {
+            return converted;
+        };
+        if phase.get_after_frames() > 0 {
+            converted = converted.with_transition(PhaseTransition::new(
+                PhaseTrigger::ElapsedFrames(u64::from(phase.get_after_frames())),
+                next_phase,
+            ));
+        }
+        if phase.get_after_attacks() > 0 {
+            converted = converted.with_transition(PhaseTransition::new(
+                PhaseTrigger::AttacksPerformed(u64::from(phase.get_after_attacks())),
+                next_phase,
+            ));
+        }
+        converted
+    }
+}
@@ -0,0 +1,608 @@
+use crate::enemy_track::EnemyTrack;
+use crate::enemy_track::future_move_commit::FutureMoveCommit;
+use crate::solver::Solver;
+use crate::solver::adaptive_difficulty::PerformanceEvent;
+use crate::solver::seeded_random::SeededRandom;
+use crate::solver::snapshot::SnapshotReader;
+use crate::solver::snapshot::SnapshotWriter;
+use crate::solver::snapshot::SolverSnapshot;
+use crate::solver_session::background_solve::BackgroundSolve;
+use crate::solver_session::background_solve::SolveWorker;
+use crate::solver_session::recording::Recording;
+use crate::solver_session::recording::RecordingEntry;
+use crate::solver_session::session_input::SessionInput;
+use std::num::NonZeroI64;
+use std::time::Duration;
+use std::time::Instant;
+
+mod background_solve;
+pub mod recording;
+pub mod session_input;
+
+// a solver together with the random state it is run with and the inputs that
+// are waiting for the next frame. every change from outside goes through
+// `apply` so that it can be recorded and replayed later.
+//
+// with a solve latency of k frames, solving is taken off the main thread. the
+// solve started at the end of frame s sees the solver as it is then, is visible
+// from frame s + k on, and only plans commits that start on s + k or later. the
+// next one is started as soon as it is visible, so the plan is brought up to
+// date every k frames. an input that is applied while a solve runs makes it
+// stale, and the main thread solves on its frame instead. performance reports
+// are held back until the solve is visible instead, so they do not make it
+// stale. the schedule only depends on the inputs and the latency, not on how
+// fast the worker is.
+#[derive(Debug)]
+pub struct SolverSession {
+    solver: Solver,
+    random: SeededRandom,
+    recording: Option<Recording>,
+    solve_latency: u64,
+    background: Option<BackgroundSolve>,
+    held_reports: Vec<PerformanceEvent>,
+    fallback_solves: u64,
+    worker: Option<SolveWorker>,
+    time_budget: Option<Duration>,
+}
+
+impl SolverSession {
+    #[must_use]
+    pub fn new(seed: u64) -> Self {
+        Self {
+            solver: Solver::new(),
+            random: SeededRandom::new(seed),
+            recording: None,
+            solve_latency: 0,
+            background: None,
+            held_reports: vec![],
+            fallback_solves: 0,
+            worker: None,
+            time_budget: None,
+        }
+    }
+    #[must_use]
+    pub fn get_solver(&self) -> &Solver {
+        &self.solver
+    }
+    // how often a background solve was stale and the main thread solved on its
+    // frame instead.
+    #[must_use]
+    pub fn get_fallback_solves(&self) -> u64 {
+        self.fallback_solves
+    }
+    #[must_use]
+    pub fn time_now(&self) -> u64 {
+        self.solver.time_now_frames()
+    }
+    // one physics frame.
+    pub fn step(&mut self) {
+        self.solver.update_latest_nonpast();
+        if self.solve_latency == 0 {
+            let mut budget = self.solver.get_step_budget();
+            if let Some(time_budget) = self.time_budget {
+                budget = budget.until(Instant::now() + time_budget);
+            }
+            self.solver.solve_within(&mut self.random, budget);
+        } else {
+            self.finish_background_solve();
+        }
+        self.solver.tick();
+        if self.solve_latency > 0 && self.background.is_none() {
+            self.start_background_solve(self.time_now() + self.solve_latency - 1);
+        }
+        if self.recording.is_some() {
+            let started = self.started_this_frame();
+            if let Some(recording) = &mut self.recording {
+                recording.push(RecordingEntry::Step(started));
+            }
+        }
+    }
+    fn finish_background_solve(&mut self) {
+        if self
+            .background
+            .as_ref()
+            .is_none_or(|background| background.get_due() != self.time_now())
+        {
+            return;
+        }
+        if let Some((solver, random)) = self.background.take().and_then(BackgroundSolve::finish) {
+            self.solver = *solver;
+            self.random = random;
+        } else {
+            self.fallback_solves += 1;
+            self.solver.solve(&mut self.random);
+        }
+        self.report_held();
+    }
+    // the running solve keeps the difficulty it started with, the report is
+    // handed over once it is visible.
+    fn report_performance(&mut self, event: PerformanceEvent) {
+        if self.background.is_some() {
+            self.held_reports.push(event);
+        } else {
+            self.solver.report_performance(event);
+        }
+    }
+    // the reports only feed the adaptive difficulty, which `tick` applies.
+    fn report_held(&mut self) {
+        for event in self.held_reports.drain(..) {
+            self.solver.report_performance(event);
+        }
+    }
+    fn start_background_solve(&mut self, due: u64) {
+        if self.worker.is_none() {
+            self.worker = SolveWorker::spawn();
+        }
+        self.background = Some(BackgroundSolve::start(
+            self.worker.as_ref(),
+            &self.solver,
+            self.random,
+            due,
+        ));
+    }
+    // the longest a frame may spend solving on the main thread, on top of the
+    // step budget. unlike everything else this is not an input, as what gets
+    // planned then depends on how fast the machine is. sessions that are
+    // recorded or kept in sync between peers have to leave it off.
+    pub fn set_time_budget(&mut self, time_budget: Option<Duration>) {
+        self.time_budget = time_budget;
+    }
+    // the attack index every track started on the frame that was just stepped.
+    #[must_use]
+    pub fn started_this_frame(&self) -> Vec<(NonZeroI64, usize)> {
+        self.solver
+            .track_ids()
+            .filter_map(|id| {
+                self.get_commit_on_this_frame(id)
+                    .map(|commit| (id, commit.get_index()))
+            })
+            .collect()
+    }
+    // returns false if the input could not be applied, which is recorded as
+    // well since it fails the same way on replay.
+    pub fn apply(&mut self, input: SessionInput) -> bool {
+        self.note_input(&input);
+        match input {
+            SessionInput::AddTrack {
+                id,
+                attacks,
+                resource_pool,
+                phases,
+            } => {
+                let mut track = EnemyTrack::new(attacks);
+                track.set_resource_pool(resource_pool);
+                track.set_phases(phases);
+                self.solver.add_track(id, track);
+                true
+            }
+            SessionInput::RemoveTrack(id) => {
+                self.solver.remove_track(id);
+                true
+            }
+            SessionInput::CommitMoveNow { id, index } => self.commit_move_now(id, index),
+            SessionInput::SetAttackValidity { id, index, valid } => {
+                self.set_validity(id, Some(index), valid)
+            }
+            SessionInput::ResetAttacksValidity { id, valid } => self.set_validity(id, None, valid),
+            SessionInput::PushAttack { id, attack } => {
+                if self.solver.find_track(id).is_none() {
+                    return false;
+                }
+                self.solver.get_track_mut(id).push_attack(attack);
+                true
+            }
+            SessionInput::ReplaceAttack { id, index, attack } => {
+                self.solver.replace_attack(id, index, attack)
+            }
+            SessionInput::RemoveAttack { id, index } => self.solver.remove_attack(id, index),
+            SessionInput::EnterPhase { id, phase } => self.solver.enter_phase(id, phase),
+            SessionInput::ReportPerformance(event) => {
+                self.report_performance(event);
+                true
+            }
+            SessionInput::SetDifficulty(difficulty) => {
+                self.solver.set_difficulty(difficulty);
+                true
+            }
+            SessionInput::SetAdaptiveDifficulty(adaptive) => {
+                self.solver.set_adaptive_difficulty(adaptive);
+                true
+            }
+            SessionInput::SetPlanningHorizon(frames) => {
+                self.solver.set_planning_horizon(frames);
+                true
+            }
+            SessionInput::SetFillerSlack(frames) => {
+                self.solver.set_filler_slack(frames);
+                true
+            }
+            SessionInput::SetRemovalGrace(frames) => {
+                self.solver.set_removal_grace(frames);
+                true
+            }
+            SessionInput::SetStepBudget(steps) => {
+                self.solver.set_step_budget(steps);
+                true
+            }
+            SessionInput::SetLeadHandoff(handoff) => {
+                self.solver.set_lead_handoff(handoff);
+                true
+            }
+            SessionInput::SetSolveLatency(frames) => {
+                self.set_solve_latency(frames);
+                true
+            }
+            SessionInput::SetSeed(seed) => {
+                self.random = SeededRandom::new(seed);
+                true
+            }
+            SessionInput::RestoreSnapshot(bytes) => self.restore_snapshot(&bytes),
+        }
+    }
+    // the running solve did not see the input, so its result can not be used.
+    // performance reports are held back until it is visible instead.
+    fn note_input(&mut self, input: &SessionInput) {
+        if let Some(recording) = &mut self.recording {
+            recording.push(RecordingEntry::Input(input.clone()));
+        }
+        if let Some(background) = &mut self.background
+            && !matches!(input, SessionInput::ReportPerformance(_))
+        {
+            background.mark_stale();
+        }
+    }
+    // a solve that is already running stays due on its frame.
+    fn set_solve_latency(&mut self, frames: u64) {
+        self.solve_latency = frames;
+        if frames == 0 {
+            self.background = None;
+            self.report_held();
+        }
+    }
+    // sets every attack of the track without an index.
+    fn set_validity(&mut self, id: NonZeroI64, index: Option<usize>, valid: bool) -> bool {
+        match index {
+            Some(index) if self.has_attack(id, index) => {
+                self.solver.get_track_mut(id).set_validity(index, valid);
+            }
+            None if self.solver.find_track(id).is_some() => {
+                self.solver.get_track_mut(id).reset_validity(valid);
+            }
+            _ => return false,
+        }
+        if !valid {
+            self.solver.drop_invalid_commits(id);
+        }
+        true
+    }
+    fn has_attack(&self, id: NonZeroI64, index: usize) -> bool {
+        self.solver
+            .find_track(id)
+            .is_some_and(|track| index < track.attacks().len())
+    }
+    fn commit_move_now(&mut self, id: NonZeroI64, index: usize) -> bool {
+        let time_now = self.time_now();
+        if !self.has_attack(id, index) || !self.solver.all_tracks_actionable(time_now) {
+            return false;
+        }
+        if self
+            .solver
+            .get_track_mut(id)
+            .commit_by_index(index, time_now, time_now)
+        {
+            self.solver.change_lead(id);
+            return true;
+        }
+        false
+    }
+    // everything needed to go back to this frame with `restore_snapshot`, as
+    // long as the same tracks are added when it is restored.
+    //
+    // a solve that is running is saved as the frame it is due on, restoring
+    // starts it again from the restored solver, which gives the same result.
+    #[must_use]
+    pub fn save_snapshot(&self) -> Vec<u8> {
+        let mut writer = SnapshotWriter::default();
+        self.write_state(&mut writer);
+        let mut bytes = writer.into_bytes();
+        bytes.extend_from_slice(self.solver.snapshot(self.random).as_bytes());
+        bytes
+    }
+    // cheap to compare with other peers every frame to find desyncs early.
+    #[must_use]
+    pub fn state_hash(&self) -> u64 {
+        let mut writer = SnapshotWriter::hashing();
+        self.write_state(&mut writer);
+        writer.write(&self.solver.state_hash(self.random));
+        writer.finish_hash().unwrap_or_default()
+    }
+    fn write_state(&self, writer: &mut SnapshotWriter) {
+        writer.write(&self.solve_latency);
+        writer.write(
+            &self
+                .background
+                .as_ref()
+                .map(|background| (background.get_due(), background.is_stale())),
+        );
+        writer.write(&self.held_reports);
+    }
+    fn restore_snapshot(&mut self, bytes: &[u8]) -> bool {
+        let mut reader = SnapshotReader::new(bytes);
+        let (Some(solve_latency), Some(background), Some(held_reports)) = (
+            reader.read::<u64>(),
+            reader.read::<Option<(u64, bool)>>(),
+            reader.read::<Vec<PerformanceEvent>>(),
+        ) else {
+            return false;
+        };
+        let snapshot = SolverSnapshot::from_bytes(reader.remaining().to_vec());
+        if !self.solver.restore(&snapshot, &mut self.random) {
+            return false;
+        }
+        self.solve_latency = solve_latency;
+        self.held_reports = held_reports;
+        self.background = None;
+        match background {
+            Some((due, true)) => self.background = Some(BackgroundSolve::stale(due)),
+            Some((due, false)) => self.start_background_solve(due),
+            None => {}
+        }
+        true
+    }
+    // starts a new recording from the current state, dropping any old one.
+    pub fn start_recording(&mut self) {
+        let tracks = self
+            .solver
+            .track_ids()
+            .map(|id| (id, self.solver.get_track(id).attacks().cloned().collect()))
+            .collect();
+        self.recording = Some(Recording::new(tracks, self.save_snapshot()));
+    }
+    pub fn stop_recording(&mut self) -> Option<Recording> {
+        self.recording.take()
+    }
+    pub fn get_latest_nonpast_commit(&self, id: NonZeroI64) -> Option<&FutureMoveCommit> {
+        self.solver.get_track(id).latest_nonpast_commit()
+    }
+    pub fn get_commit_on_this_frame(&self, id: NonZeroI64) -> Option<&FutureMoveCommit> {
+        // subtract one because the end of the processing cycle for the solver increments and so checking
+        // when you are outside of that cycle, you will need to effectively undo that.
+        let time_now = self.time_now().checked_sub(1);
+        self.get_latest_nonpast_commit(id)
+            .filter(|v| time_now.is_some_and(|t| v.get_start_frame().eq(&t)))
+    }
+    pub fn get_active_commit(&self, id: NonZeroI64) -> Option<&FutureMoveCommit> {
+        // subtract one because the end of the processing cycle for the solver increments and so checking
+        // when you are outside of that cycle, you will need to effectively undo that.
+        let time_now = self.time_now().checked_sub(1);
+        self.get_latest_nonpast_commit(id)
+            .filter(|v| time_now.is_some_and(|t| v.get_start_frame().le(&t)))
+    }
+}
+
+#[cfg(test)]
+mod solver_session_tests {
+    use super::*;
+    use crate::attack::Attack;
+    use crate::replay::ReplayError;
+    use crate::replay::replay;
+    use crate::replay::replay_recording;
+    use crate::solver::adaptive_difficulty::AdaptiveDifficulty;
+    use crate::solver::difficulty::DifficultyPreset;
+
+    fn session_with_follower() -> (SolverSession, NonZeroI64, NonZeroI64) {
+        let lead_id = NonZeroI64::new(7).unwrap();
+        let follower_id = NonZeroI64::new(2).unwrap();
+        let mut session = SolverSession::new(5);
+        session.apply(SessionInput::AddTrack {
+            id: lead_id,
+            attacks: vec![Attack::new_expect(40, vec![30], vec![10, 20])],
+            resource_pool: None,
+            phases: None,
+        });
+        session.apply(SessionInput::AddTrack {
+            id: follower_id,
+            attacks: vec![
+                Attack::new_expect(8, vec![4], vec![]),
+                Attack::new_expect(6, vec![3], vec![]),
+            ],
+            resource_pool: None,
+            phases: None,
+        });
+        (session, lead_id, follower_id)
+    }
+
+    fn record_session(solve_latency: u64) -> Recording {
+        let (mut session, lead_id, follower_id) = session_with_follower();
+        for _ in 0..10 {
+            session.step();
+        }
+        session.start_recording();
+        session.apply(SessionInput::SetSolveLatency(solve_latency));
+        for frame in 0..200 {
+            if frame % 50 == 0 {
+                assert!(session.apply(SessionInput::CommitMoveNow {
+                    id: lead_id,
+                    index: 0,
+                }));
+            }
+            if frame == 75 {
+                session.apply(SessionInput::SetAttackValidity {
+                    id: follower_id,
+                    index: 1,
+                    valid: false,
+                });
+            }
+            if frame == 120 {
+                session.apply(SessionInput::PushAttack {
+                    id: follower_id,
+                    attack: Attack::new_expect(12, vec![6], vec![]),
+                });
+                session.apply(SessionInput::RemoveAttack {
+                    id: follower_id,
+                    index: 0,
+                });
+            }
+            session.step();
+        }
+        session.stop_recording().unwrap()
+    }
+
+    #[test]
+    fn unknown_tracks_are_rejected() {
+        let (mut session, _, follower_id) = session_with_follower();
+        let unknown = NonZeroI64::new(100).unwrap();
+        assert!(!session.apply(SessionInput::CommitMoveNow {
+            id: unknown,
+            index: 0
+        }));
+        assert!(!session.apply(SessionInput::SetAttackValidity {
+            id: follower_id,
+            index: 2,
+            valid: false,
+        }));
+    }
+
+    #[test]
+    fn recording_replays() {
+        for solve_latency in [0, 3] {
+            let recording = record_session(solve_latency);
+            assert!(recording.get_entries().iter().any(
+                |entry| matches!(entry, RecordingEntry::Step(started) if !started.is_empty())
+            ));
+            assert_eq!(replay(&recording.to_bytes()), Ok(200));
+        }
+    }
+
+    #[test]
+    fn solve_latency_delays_plans() {
+        let (mut session, lead_id, follower_id) = session_with_follower();
+        session.apply(SessionInput::SetSolveLatency(5));
+        assert!(session.apply(SessionInput::CommitMoveNow {
+            id: lead_id,
+            index: 0,
+        }));
+        for _ in 0..5 {
+            session.step();
+        }
+        let follower = session.get_solver().get_track(follower_id);
+        assert_eq!(follower.future_commits().count(), 0);
+
+        session.step();
+        let follower = session.get_solver().get_track(follower_id);
+        assert!(follower.future_commits().count() > 0);
+        assert!(
+            follower
+                .future_commits()
+                .all(|commit| commit.get_start_frame() >= 5)
+        );
+    }
+
+    #[test]
+    fn performance_reports_keep_solve_running() {
+        let (mut session, lead_id, follower_id) = session_with_follower();
+        session.apply(SessionInput::SetAdaptiveDifficulty(Some(
+            AdaptiveDifficulty::new(DifficultyPreset::Easy.into(), DifficultyPreset::Hard.into()),
+        )));
+        session.apply(SessionInput::SetSolveLatency(4));
+        for frame in 0..120 {
+            if frame % 40 == 0 {
+                session.apply(SessionInput::CommitMoveNow {
+                    id: lead_id,
+                    index: 0,
+                });
+            }
+            session.apply(SessionInput::ReportPerformance(
+                PerformanceEvent::ParrySuccess,
+            ));
+            session.step();
+        }
+        // only the later commits of the lead make the solves they land in
+        // stale, the first one is made before any solve runs.
+        assert_eq!(session.get_fallback_solves(), 2);
+
+        for _ in 0..8 {
+            session.apply(SessionInput::SetAttackValidity {
+                id: follower_id,
+                index: 0,
+                valid: true,
+            });
+            session.step();
+        }
+        assert_eq!(session.get_fallback_solves(), 4);
+    }
+
+    #[test]
+    fn restoring_restarts_running_solve() {
+        let (mut session, lead_id, _) = session_with_follower();
+        session.apply(SessionInput::SetAdaptiveDifficulty(Some(
+            AdaptiveDifficulty::new(DifficultyPreset::Easy.into(), DifficultyPreset::Hard.into()),
+        )));
+        session.apply(SessionInput::SetSolveLatency(4));
+        let mut snapshot = None;
+        let mut hashes = vec![];
+        for frame in 0..120 {
+            if frame % 40 == 0 {
+                session.apply(SessionInput::CommitMoveNow {
+                    id: lead_id,
+                    index: 0,
+                });
+            }
+            session.apply(SessionInput::ReportPerformance(
+                PerformanceEvent::ParryMissed,
+            ));
+            session.step();
+            if frame == 42 {
+                snapshot = Some(session.save_snapshot());
+            }
+            if frame > 42 {
+                hashes.push(session.state_hash());
+            }
+        }
+
+        let (mut restored, lead_id, _) = session_with_follower();
+        assert!(restored.apply(SessionInput::RestoreSnapshot(snapshot.unwrap())));
+        for frame in 43..120 {
+            if frame % 40 == 0 {
+                restored.apply(SessionInput::CommitMoveNow {
+                    id: lead_id,
+                    index: 0,
+                });
+            }
+            restored.apply(SessionInput::ReportPerformance(
+                PerformanceEvent::ParryMissed,
+            ));
+            restored.step();
+            assert_eq!(restored.state_hash(), hashes[frame - 43]);
+        }
+    }
+
+    #[test]
+    fn tampered_recording_desyncs() {
+        let recording = record_session(0);
+        let mut tampered = Recording::new(
+            recording.get_tracks().to_vec(),
+            recording.get_initial_state().to_vec(),
+        );
+        let mut frame = 10;
+        let mut desync_frame = None;
+        for entry in recording.get_entries() {
+            match entry {
+                RecordingEntry::Step(started) if !started.is_empty() && desync_frame.is_none() => {
+                    desync_frame = Some(frame);
+                    tampered.push(RecordingEntry::Step(vec![]));
+                }
+                _ => tampered.push(entry.clone()),
+            }
+            if matches!(entry, RecordingEntry::Step(_)) {
+                frame += 1;
+            }
+        }
+        assert_eq!(
+            replay_recording(&tampered),
+            Err(ReplayError::Desync {
+                frame: desync_frame.unwrap()
+            })
+        );
+    }
+}
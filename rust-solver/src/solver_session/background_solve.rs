@@ -0,0 +1,164 @@
+use crate::solver::Solver;
+use crate::solver::seeded_random::SeededRandom;
+use std::sync::mpsc;
+use std::thread;
+
+type Solved = (Box<Solver>, SeededRandom);
+
+// a copy of the solver to be solved on `due`, and where to send it when done.
+type Job = (Box<Solver>, SeededRandom, u64, mpsc::Sender<Solved>);
+
+// a thread that solves copies of the solver one after the other.
+#[derive(Debug)]
+pub struct SolveWorker {
+    jobs: mpsc::Sender<Job>,
+}
+
+impl SolveWorker {
+    // none without threads, solves are then run on the main thread when due.
+    pub fn spawn() -> Option<Self> {
+        if cfg!(feature = "nothreads") {
+            return None;
+        }
+        let (jobs, received) = mpsc::channel::<Job>();
+        // the thread ends once the worker is dropped and its last job is done.
+        thread::Builder::new()
+            .name("attack-solver".into())
+            .spawn(move || {
+                for (solver, random, due, result) in received {
+                    // nobody is waiting for it anymore if this fails.
+                    let _ = result.send(solve_on(solver, random, due));
+                }
+            })
+            .ok()?;
+        Some(Self { jobs })
+    }
+}
+
+// a solve of a copy of the solver, started at the end of a frame and finished
+// on `due`. the copy is caught up to `due` the same way the main thread steps
+// without solving, so the result is what solving on the main thread on `due`
+// gives, no matter how long the worker took. once an input changes the solver
+// that no longer holds, and the result is thrown away.
+#[derive(Debug)]
+pub struct BackgroundSolve {
+    due: u64,
+    job: Option<SolveJob>,
+}
+
+#[derive(Debug)]
+enum SolveJob {
+    Running(mpsc::Receiver<Solved>),
+    // no worker to run it, it is solved when it is finished.
+    Waiting(Solved),
+}
+
+impl BackgroundSolve {
+    pub fn start(
+        worker: Option<&SolveWorker>,
+        solver: &Solver,
+        random: SeededRandom,
+        due: u64,
+    ) -> Self {
+        let copy = Box::new(solver.clone());
+        let job = match worker {
+            Some(worker) => {
+                let (result, received) = mpsc::channel();
+                match worker.jobs.send((copy, random, due, result)) {
+                    Ok(()) => SolveJob::Running(received),
+                    Err(mpsc::SendError((copy, random, ..))) => SolveJob::Waiting((copy, random)),
+                }
+            }
+            None => SolveJob::Waiting((copy, random)),
+        };
+        Self {
+            due,
+            job: Some(job),
+        }
+    }
+    // a solve that has been made stale already, for restoring one from a snapshot.
+    pub fn stale(due: u64) -> Self {
+        Self { due, job: None }
+    }
+    pub fn get_due(&self) -> u64 {
+        self.due
+    }
+    pub fn is_stale(&self) -> bool {
+        self.job.is_none()
+    }
+    pub fn mark_stale(&mut self) {
+        self.job = None;
+    }
+    // waits for the worker if it is not done yet. none if the solve is stale
+    // or the worker stopped without a result.
+    pub fn finish(self) -> Option<Solved> {
+        match self.job? {
+            SolveJob::Running(received) => received.recv().ok(),
+            SolveJob::Waiting((solver, random)) => Some(solve_on(solver, random, self.due)),
+        }
+    }
+}
+
+// steps the solver up to `due` without solving, then solves it on `due`.
+fn solve_on(mut solver: Box<Solver>, mut random: SeededRandom, due: u64) -> Solved {
+    while solver.time_now_frames() < due {
+        solver.update_latest_nonpast();
+        solver.tick();
+    }
+    solver.update_latest_nonpast();
+    solver.solve(&mut random);
+    (solver, random)
+}
+
+#[cfg(test)]
+mod background_solve_tests {
+    use super::*;
+    use crate::attack::Attack;
+    use crate::enemy_track::EnemyTrack;
+    use crate::tests::id;
+    use crate::tests::solver_with_lead;
+
+    fn solver_with_followers() -> Solver {
+        let mut solver = solver_with_lead(Attack::new_expect(40, vec![30], vec![10, 20]));
+        for track_id in 1..=4 {
+            solver.add_track(
+                id(track_id),
+                EnemyTrack::new(vec![Attack::new_expect(6, vec![3], vec![])]),
+            );
+        }
+        solver
+    }
+
+    #[test]
+    fn matches_solving_when_due() {
+        let random = SeededRandom::new(8);
+        let (mut expected, mut expected_random) = (solver_with_followers(), random);
+        for _ in 0..3 {
+            expected.update_latest_nonpast();
+            expected.tick();
+        }
+        expected.update_latest_nonpast();
+        expected.solve(&mut expected_random);
+
+        for worker in [SolveWorker::spawn(), None] {
+            let background =
+                BackgroundSolve::start(worker.as_ref(), &solver_with_followers(), random, 3);
+            let (solved, solved_random) = background.finish().unwrap();
+            assert_eq!(solved.time_now_frames(), 3);
+            assert_eq!(
+                solved.state_hash(solved_random),
+                expected.state_hash(expected_random)
+            );
+        }
+    }
+
+    #[test]
+    fn stale_solves_have_no_result() {
+        let mut background =
+            BackgroundSolve::start(None, &solver_with_followers(), SeededRandom::new(1), 2);
+        assert!(!background.is_stale());
+        background.mark_stale();
+        assert!(background.is_stale());
+        assert!(background.finish().is_none());
+    }
+}
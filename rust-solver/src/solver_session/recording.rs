@@ -0,0 +1,126 @@
+use crate::attack::Attack;
+use crate::solver::snapshot::SnapshotReader;
+use crate::solver::snapshot::SnapshotState;
+use crate::solver::snapshot::SnapshotWriter;
+use crate::solver_session::session_input::SessionInput;
+use std::num::NonZeroI64;
+
+const RECORDING_MAGIC: [u8; 4] = *b"ASRP";
+const RECORDING_VERSION: u64 = 4;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RecordingEntry {
+    Input(SessionInput),
+    // one physics frame, with the attack index every track started on it,
+    // sorted by track id.
+    Step(Vec<(NonZeroI64, usize)>),
+}
+
+impl SnapshotState for RecordingEntry {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        match self {
+            Self::Input(input) => {
+                writer.write(&0u8);
+                writer.write(input);
+            }
+            Self::Step(started) => {
+                writer.write(&1u8);
+                writer.write(started);
+            }
+        }
+    }
+    fn read(reader: &mut SnapshotReader) -> Option<Self> {
+        match reader.read::<u8>()? {
+            0 => reader.read().map(Self::Input),
+            1 => reader.read().map(Self::Step),
+            _ => None,
+        }
+    }
+}
+
+// the tracks and session state at the time recording started, followed by
+// every input and frame after it in order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recording {
+    tracks: Vec<(NonZeroI64, Vec<Attack>)>,
+    initial_state: Vec<u8>,
+    entries: Vec<RecordingEntry>,
+}
+
+impl Recording {
+    #[must_use]
+    pub fn new(tracks: Vec<(NonZeroI64, Vec<Attack>)>, initial_state: Vec<u8>) -> Self {
+        Self {
+            tracks,
+            initial_state,
+            entries: vec![],
+        }
+    }
+    pub fn push(&mut self, entry: RecordingEntry) {
+        self.entries.push(entry);
+    }
+    #[must_use]
+    pub fn get_tracks(&self) -> &[(NonZeroI64, Vec<Attack>)] {
+        &self.tracks
+    }
+    #[must_use]
+    pub fn get_initial_state(&self) -> &[u8] {
+        &self.initial_state
+    }
+    #[must_use]
+    pub fn get_entries(&self) -> &[RecordingEntry] {
+        &self.entries
+    }
+    #[must_use]
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = SnapshotWriter::default();
+        for byte in RECORDING_MAGIC {
+            writer.write(&byte);
+        }
+        writer.write(&RECORDING_VERSION);
+        writer.write(&self.tracks);
+        writer.write(&self.initial_state);
+        writer.write(&self.entries);
+        writer.into_bytes()
+    }
+    #[must_use]
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        let mut reader = SnapshotReader::new(bytes);
+        for byte in RECORDING_MAGIC {
+            if reader.read::<u8>()? != byte {
+                return None;
+            }
+        }
+        if reader.read::<u64>()? != RECORDING_VERSION {
+            return None;
+        }
+        let recording = Self {
+            tracks: reader.read()?,
+            initial_state: reader.read()?,
+            entries: reader.read()?,
+        };
+        reader.is_empty().then_some(recording)
+    }
+}
+
+#[cfg(test)]
+mod recording_tests {
+    use super::*;
+
+    #[test]
+    fn bytes_round_trip() {
+        let id = NonZeroI64::new(3).unwrap();
+        let attack = Attack::new_expect(10, vec![5], vec![2]).with_cost(4);
+        let mut recording = Recording::new(vec![(id, vec![attack])], vec![0, 1, 2]);
+        recording.push(RecordingEntry::Input(SessionInput::CommitMoveNow {
+            id,
+            index: 0,
+        }));
+        recording.push(RecordingEntry::Step(vec![(id, 0)]));
+
+        let bytes = recording.to_bytes();
+        assert_eq!(Recording::from_bytes(&bytes), Some(recording));
+        assert_eq!(Recording::from_bytes(&bytes[1..]), None);
+        assert_eq!(Recording::from_bytes(&bytes[..bytes.len() - 1]), None);
+    }
+}
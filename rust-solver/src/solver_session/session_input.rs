@@ -0,0 +1,221 @@
+use crate::attack::Attack;
+use crate::enemy_track::resource_pool::ResourcePool;
+use crate::enemy_track::track_phases::TrackPhases;
+use crate::solver::adaptive_difficulty::AdaptiveDifficulty;
+use crate::solver::adaptive_difficulty::PerformanceEvent;
+use crate::solver::difficulty::Difficulty;
+use crate::solver::lead_handoff::LeadHandoff;
+use crate::solver::snapshot::SnapshotReader;
+use crate::solver::snapshot::SnapshotState;
+use crate::solver::snapshot::SnapshotWriter;
+use std::num::NonZeroI64;
+
+// everything from outside the solver that can change what it schedules.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SessionInput {
+    AddTrack {
+        id: NonZeroI64,
+        attacks: Vec<Attack>,
+        resource_pool: Option<ResourcePool>,
+        phases: Option<TrackPhases>,
+    },
+    RemoveTrack(NonZeroI64),
+    CommitMoveNow {
+        id: NonZeroI64,
+        index: usize,
+    },
+    SetAttackValidity {
+        id: NonZeroI64,
+        index: usize,
+        valid: bool,
+    },
+    ResetAttacksValidity {
+        id: NonZeroI64,
+        valid: bool,
+    },
+    PushAttack {
+        id: NonZeroI64,
+        attack: Attack,
+    },
+    ReplaceAttack {
+        id: NonZeroI64,
+        index: usize,
+        attack: Attack,
+    },
+    RemoveAttack {
+        id: NonZeroI64,
+        index: usize,
+    },
+    EnterPhase {
+        id: NonZeroI64,
+        phase: usize,
+    },
+    ReportPerformance(PerformanceEvent),
+    SetDifficulty(Difficulty),
+    SetAdaptiveDifficulty(Option<AdaptiveDifficulty>),
+    SetPlanningHorizon(u64),
+    SetFillerSlack(u64),
+    SetRemovalGrace(u64),
+    SetSolveLatency(u64),
+    SetStepBudget(u64),
+    SetLeadHandoff(LeadHandoff),
+    SetSeed(u64),
+    RestoreSnapshot(Vec<u8>),
+}
+
+impl SnapshotState for SessionInput {
+    fn write(&self, writer: &mut SnapshotWriter) {
+        match self {
+            Self::AddTrack {
+                id,
+                attacks,
+                resource_pool,
+                phases,
+            } => {
+                writer.write(&0u8);
+                writer.write(id);
+                writer.write(attacks);
+                writer.write(resource_pool);
+                writer.write(phases);
+            }
+            Self::RemoveTrack(id) => {
+                writer.write(&1u8);
+                writer.write(id);
+            }
+            Self::CommitMoveNow { id, index } => {
+                writer.write(&2u8);
+                writer.write(id);
+                writer.write(index);
+            }
+            Self::SetAttackValidity { id, index, valid } => {
+                writer.write(&3u8);
+                writer.write(id);
+                writer.write(index);
+                writer.write(valid);
+            }
+            Self::ResetAttacksValidity { id, valid } => {
+                writer.write(&4u8);
+                writer.write(id);
+                writer.write(valid);
+            }
+            Self::ReportPerformance(event) => {
+                writer.write(&5u8);
+                writer.write(event);
+            }
+            Self::SetDifficulty(difficulty) => {
+                writer.write(&6u8);
+                writer.write(difficulty);
+            }
+            Self::SetAdaptiveDifficulty(adaptive) => {
+                writer.write(&7u8);
+                writer.write(adaptive);
+            }
+            Self::SetPlanningHorizon(frames) => {
+                writer.write(&8u8);
+                writer.write(frames);
+            }
+            Self::SetFillerSlack(frames) => {
+                writer.write(&9u8);
+                writer.write(frames);
+            }
+            Self::SetSeed(seed) => {
+                writer.write(&10u8);
+                writer.write(seed);
+            }
+            Self::RestoreSnapshot(bytes) => {
+                writer.write(&11u8);
+                writer.write(bytes);
+            }
+            Self::PushAttack { id, attack } => {
+                writer.write(&12u8);
+                writer.write(id);
+                writer.write(attack);
+            }
+            Self::ReplaceAttack { id, index, attack } => {
+                writer.write(&13u8);
+                writer.write(id);
+                writer.write(index);
+                writer.write(attack);
+            }
+            Self::RemoveAttack { id, index } => {
+                writer.write(&14u8);
+                writer.write(id);
+                writer.write(index);
+            }
+            Self::EnterPhase { id, phase } => {
+                writer.write(&15u8);
+                writer.write(id);
+                writer.write(phase);
+            }
+            Self::SetRemovalGrace(frames) => {
+                writer.write(&16u8);
+                writer.write(frames);
+            }
+            Self::SetSolveLatency(frames) => {
+                writer.write(&17u8);
+                writer.write(frames);
+            }
+            Self::SetStepBudget(steps) => {
+                writer.write(&18u8);
+                writer.write(steps);
+            }
+            Self::SetLeadHandoff(handoff) => {
+                writer.write(&19u8);
+                writer.write(handoff);
+            }
+        }
+    }
+    fn read(reader: &mut SnapshotReader) -> Option<Self> {
+        Some(match reader.read::<u8>()? {
+            0 => Self::AddTrack {
+                id: reader.read()?,
+                attacks: reader.read()?,
+                resource_pool: reader.read()?,
+                phases: reader.read()?,
+            },
+            1 => Self::RemoveTrack(reader.read()?),
+            2 => Self::CommitMoveNow {
+                id: reader.read()?,
+                index: reader.read()?,
+            },
+            3 => Self::SetAttackValidity {
+                id: reader.read()?,
+                index: reader.read()?,
+                valid: reader.read()?,
+            },
+            4 => Self::ResetAttacksValidity {
+                id: reader.read()?,
+                valid: reader.read()?,
+            },
+            5 => Self::ReportPerformance(reader.read()?),
+            6 => Self::SetDifficulty(reader.read()?),
+            7 => Self::SetAdaptiveDifficulty(reader.read()?),
+            8 => Self::SetPlanningHorizon(reader.read()?),
+            9 => Self::SetFillerSlack(reader.read()?),
+            10 => Self::SetSeed(reader.read()?),
+            11 => Self::RestoreSnapshot(reader.read()?),
+            12 => Self::PushAttack {
+                id: reader.read()?,
+                attack: reader.read()?,
+            },
+            13 => Self::ReplaceAttack {
+                id: reader.read()?,
+                index: reader.read()?,
+                attack: reader.read()?,
+            },
+            14 => Self::RemoveAttack {
+                id: reader.read()?,
+                index: reader.read()?,
+            },
+            15 => Self::EnterPhase {
+                id: reader.read()?,
+                phase: reader.read()?,
+            },
+            16 => Self::SetRemovalGrace(reader.read()?),
+            17 => Self::SetSolveLatency(reader.read()?),
+            18 => Self::SetStepBudget(reader.read()?),
+            19 => Self::SetLeadHandoff(reader.read()?),
+            _ => return None,
+        })
+    }
+}